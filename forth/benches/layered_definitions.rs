@@ -0,0 +1,32 @@
+//! Defines chains of words where every layer calls the previous one twice
+//! (`: w1 w0 w0 ;`, `: w2 w1 w1 ;`, ...). Inlining each definition makes the
+//! cost double per layer, while compiled definitions only add a constant
+//! amount of code, so the time per layer should stay flat as depth grows.
+//!
+//! Run with `cargo bench`.
+
+use forth::Forth;
+use std::time::{Duration, Instant};
+
+fn define_layers(depth: usize) -> Duration {
+    let mut f = Forth::new();
+    let start = Instant::now();
+    f.eval(": w0 1 drop ;").unwrap();
+    for layer in 1..=depth {
+        f.eval(&format!(": w{} w{} w{} ;", layer, layer - 1, layer - 1)).unwrap();
+    }
+    start.elapsed()
+}
+
+fn main() {
+    println!("{:>6} | {:>12} | {:>14}", "depth", "total", "per layer");
+    for &depth in &[16, 64, 256, 1024, 4096] {
+        let elapsed = define_layers(depth);
+        println!(
+            "{:>6} | {:>12?} | {:>14?}",
+            depth,
+            elapsed,
+            elapsed / depth as u32
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use std::{convert::TryFrom, fmt, str::FromStr};
+
+/// A number that can be stored on the data stack.
+///
+/// All arithmetic is checked, returning `None` when the result does not fit in the cell.
+/// Division and remainder are only called with a non-zero divisor.
+pub trait Cell: Clone + Ord + fmt::Debug + fmt::Display + FromStr {
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    fn checked_sub(&self, other: &Self) -> Option<Self>;
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+    fn checked_div(&self, other: &Self) -> Option<Self>;
+    fn checked_rem(&self, other: &Self) -> Option<Self>;
+    fn checked_neg(&self) -> Option<Self>;
+    /// Converts the cell to a memory address or count, if it is a valid `usize`.
+    fn to_usize(&self) -> Option<usize>;
+    fn from_usize(n: usize) -> Option<Self>;
+
+    /// Forth flags are all bits set (`-1`) for true and `0` for false.
+    fn flag(b: bool) -> Self {
+        if b {
+            Self::zero().checked_sub(&Self::one()).expect("cells must be signed")
+        } else {
+            Self::zero()
+        }
+    }
+}
+
+macro_rules! primitive_cell {
+    ($($t:ty),*) => {
+        $(
+            impl Cell for $t {
+                fn zero() -> Self {
+                    0
+                }
+
+                fn one() -> Self {
+                    1
+                }
+
+                fn checked_add(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_add(*self, *other)
+                }
+
+                fn checked_sub(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_sub(*self, *other)
+                }
+
+                fn checked_mul(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_mul(*self, *other)
+                }
+
+                fn checked_div(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_div(*self, *other)
+                }
+
+                fn checked_rem(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_rem(*self, *other)
+                }
+
+                fn checked_neg(&self) -> Option<Self> {
+                    <$t>::checked_neg(*self)
+                }
+
+                fn to_usize(&self) -> Option<usize> {
+                    usize::try_from(*self).ok()
+                }
+
+                fn from_usize(n: usize) -> Option<Self> {
+                    <$t>::try_from(n).ok()
+                }
+            }
+        )*
+    };
+}
+
+primitive_cell!(i32, i64, i128);
+
+/// Arbitrary precision cells, which never overflow.
+#[cfg(feature = "bigint")]
+impl Cell for num_bigint::BigInt {
+    fn zero() -> Self {
+        Self::from(0)
+    }
+
+    fn one() -> Self {
+        Self::from(1)
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        Some(self - other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(self * other)
+    }
+
+    fn checked_div(&self, other: &Self) -> Option<Self> {
+        Some(self / other)
+    }
+
+    fn checked_rem(&self, other: &Self) -> Option<Self> {
+        Some(self % other)
+    }
+
+    fn checked_neg(&self) -> Option<Self> {
+        Some(-self)
+    }
+
+    fn to_usize(&self) -> Option<usize> {
+        usize::try_from(self).ok()
+    }
+
+    fn from_usize(n: usize) -> Option<Self> {
+        Some(Self::from(n))
+    }
+}
@@ -1,57 +1,391 @@
 use std::{
+    convert::TryFrom,
+    fmt,
+    io::Write,
+    rc::Rc,
     str::FromStr,
     collections::HashMap,
 };
 
+mod cell;
+
+pub use cell::Cell;
+
+/// The default cell type used by `Forth::new`.
 pub type Value = i32;
 pub type ForthResult = Result<(), Error>;
 
+/// The data stack as seen by native words.
+pub type Stack<C = Value> = Vec<C>;
+
+/// The most cells `variable` and `allot` can reserve in total.
+pub const MAX_MEMORY_CELLS: usize = 1 << 20;
+
+type NativeFn<C> = Rc<dyn Fn(&mut Stack<C>) -> ForthResult>;
+
+/// A word implemented in Rust, see `Forth::register_native`.
+struct Native<C> {
+    arity: usize,
+    func: NativeFn<C>,
+}
+
+// Written out because deriving would require `C: Clone + Debug`, and closures have no `Debug`.
+impl<C> Clone for Native<C> {
+    fn clone(&self) -> Self {
+        Native { arity: self.arity, func: self.func.clone() }
+    }
+}
+
+impl<C> fmt::Debug for Native<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Native").field("arity", &self.arity).finish_non_exhaustive()
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub enum Error {
     DivisionByZero,
     StackUnderflow,
     UnknownWord,
     InvalidWord,
+    /// A control word (`else`, `then`, `until`, `loop`, `i`) was used without its opening word
+    UnmatchedControl,
+    /// A control structure (`if`, `begin`, `do`) was opened but never closed
+    UnterminatedControl,
+    /// An arithmetic result did not fit in the cell type
+    Overflow,
+    /// A memory access or `allot` outside of the allocated memory, or memory growing past
+    /// `MAX_MEMORY_CELLS`
+    InvalidAddress,
+    /// `emit` was given a value that is not a character
+    InvalidCharacter,
+    /// Writing to the output sink failed
+    Output,
+    /// One of the `Limits` set with `Forth::set_limits` was reached
+    LimitExceeded(Limit),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Limit {
+    Steps,
+    StackDepth,
+    DictionarySize,
+    RecursionDepth,
+    MemoryCells,
 }
 
+/// Bounds on the work done by the interpreter, for running untrusted input. `None` means
+/// unlimited, which is the default.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Limits {
+    /// Instructions executed for a single line
+    pub max_steps: Option<usize>,
+    pub max_stack_depth: Option<usize>,
+    /// Number of defined words, where every redefinition counts as a new word
+    pub max_dictionary_size: Option<usize>,
+    /// Number of user or native words that may be running inside each other
+    pub max_recursion_depth: Option<usize>,
+    /// Cells reserved with `variable` and `allot`, which can never exceed `MAX_MEMORY_CELLS`
+    pub max_memory_cells: Option<usize>,
+}
+
+fn exceeds(limit: Option<usize>, value: usize) -> bool {
+    limit.is_some_and(|limit| value > limit)
+}
+
+/// An executed word, as passed to the hook set with `Forth::set_trace`.
+#[derive(Debug)]
+pub struct TraceStep<'a, C> {
+    pub word: &'a str,
+    /// Number of user or native words being run when the word was executed
+    pub depth: usize,
+    pub before: &'a [C],
+    pub after: &'a [C],
+}
+
+type TraceHook<C> = Box<dyn FnMut(&TraceStep<'_, C>)>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            Error::DivisionByZero => "division by zero",
+            Error::StackUnderflow => "stack underflow",
+            Error::UnknownWord => "unknown word",
+            Error::InvalidWord => "invalid word",
+            Error::UnmatchedControl => "control word without matching opening word",
+            Error::UnterminatedControl => "unterminated control structure",
+            Error::Overflow => "arithmetic overflow",
+            Error::InvalidAddress => "invalid memory address",
+            Error::InvalidCharacter => "invalid character",
+            Error::Output => "failed to write output",
+            Error::LimitExceeded(limit) => return write!(f, "{:?} limit exceeded", limit),
+        };
+        f.write_str(message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// An `Error` together with where in the input line it happened.
+///
+/// Errors raised while running a user defined word are reported at the word of the input line
+/// that called it, with the chain of user words that were running in `call_stack`.
+#[derive(Debug, PartialEq)]
+pub struct ForthError {
+    pub kind: Error,
+    /// The offending word as written in the input
+    pub word: String,
+    /// Byte offset of `word` in the input line
+    pub offset: usize,
+    /// 1-based character column of `word` in the input line
+    pub column: usize,
+    /// Name of the word being defined, if the line was a `: name ... ;` definition
+    pub definition: Option<String>,
+    /// User words being executed when the error happened, outermost first
+    pub call_stack: Vec<String>,
+}
+
+impl fmt::Display for ForthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at column {}: {}", self.kind, self.column, self.word)?;
+        if let Some(definition) = &self.definition {
+            write!(f, " (while defining {})", definition)?;
+        }
+        if !self.call_stack.is_empty() {
+            write!(f, " (in {})", self.call_stack.join(" -> "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ForthError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.kind)
+    }
+}
+
+impl From<ForthError> for Error {
+    fn from(error: ForthError) -> Self {
+        error.kind
+    }
+}
+
+/// An error paired with the index of the token it originated from.
+type Located = (Error, usize);
+
+#[derive(Debug)]
+struct Token<'a> {
+    text: String,
+    source: &'a str,
+    offset: usize,
+    /// The text of a `." text"` string, or `None` if the closing quote is missing
+    literal: Option<String>,
+}
+
+/// Splits `input` into whitespace separated words, dropping `( comments )` and reading the
+/// text after `."` up to the closing `"` as a single token.
+fn tokenize(input: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let mut position = 0;
+    while let Some(start) = input[position..].find(|c: char| !c.is_whitespace()) {
+        let start = position + start;
+        let end = input[start..].find(char::is_whitespace).map_or(input.len(), |i| start + i);
+        let source = &input[start..end];
+        position = end;
+        match source {
+            "(" => {
+                position = input[end..].find(')').map_or(input.len(), |i| end + i + 1);
+            },
+            ".\"" => {
+                // the text starts after the single space separating it from `."`
+                let text_start = end + input[end..].chars().next().map_or(0, char::len_utf8);
+                let literal = input[text_start..].find('"').map(|i| {
+                    position = text_start + i + 1;
+                    input[text_start..text_start + i].to_string()
+                });
+                tokens.push(Token { text: source.to_string(), source, offset: start, literal });
+            },
+            _ => tokens.push(Token { text: source.to_lowercase(), source, offset: start, literal: None }),
+        }
+    }
+    tokens
+}
+
+type Dictionary = HashMap<String, usize>;
+
+/// Parsed words, each paired with the index of the token it was parsed from.
+type Words<C> = Vec<(usize, Word<C>)>;
+
+/// The token index and text of the word that ended a block.
+type Terminator<'a> = Option<(usize, &'a str)>;
+
 #[derive(Debug, Clone, PartialEq)]
-enum Word {
-    Number(Value),
+enum Word<C> {
+    Number(C),
     Operator(Operator),
+    Unary(UnaryOperator),
+    DivMod,
+    Memory(MemoryOperator),
+    Output(OutputOperator),
+    Print(Rc<str>),
     Dup,
     Drop,
     Swap,
     Over,
-    Command(String),
+    /// Consequent and alternative, with the token index of `else` if there is one
+    If(Words<C>, Words<C>, Option<usize>),
+    /// Body and the token index of `until`
+    BeginUntil(Words<C>, usize),
+    /// Body and the token index of `loop`
+    DoLoop(Words<C>, usize),
+    LoopIndex,
+    Call(usize),
+}
+
+fn is_control(s: &str) -> bool {
+    matches!(s, "if" | "else" | "then" | "begin" | "until" | "do" | "loop")
 }
 
-impl Word {
-    fn parse_string<'a>(input: &mut impl Iterator<Item=&'a str>, commands: &HashMap<String, Vec<Word>>) -> Result<Vec<Word>, Error> {
-        input.try_fold(vec![], |mut words, word| {
-            if commands.contains_key(word) {
-                words.push(Word::Command(word.to_string()));
-            } else {
-                words.push((&word).parse::<Word>()?);
+/// Words that define a new word from the name following them, which are only valid at top level.
+fn is_defining(s: &str) -> bool {
+    matches!(s, "variable" | "constant")
+}
+
+impl<C: Cell> Word<C> {
+    fn parse_string<'a>(input: &mut impl Iterator<Item=(usize, &'a Token<'a>)>, dictionary: &Dictionary) -> Result<Words<C>, Located> {
+        match Word::parse_block(input, dictionary, &[])? {
+            (words, None) => Ok(words),
+            (_, Some((index, _))) => Err((Error::UnmatchedControl, index)),
+        }
+    }
+
+    /// Parses words until one of `terminators` is found, returning the parsed words together
+    /// with the terminator that ended the block, or `None` if the input ran out.
+    ///
+    /// User words are resolved to their current code address here, so that later
+    /// redefinitions do not affect words that have already been compiled.
+    fn parse_block<'a>(input: &mut impl Iterator<Item=(usize, &'a Token<'a>)>, dictionary: &Dictionary, terminators: &[&str]) -> Result<(Words<C>, Terminator<'a>), Located> {
+        let mut words = vec![];
+        while let Some((index, token)) = input.next() {
+            let word = token.text.as_str();
+            if terminators.contains(&word) {
+                return Ok((words, Some((index, word))));
             }
-            Ok(words)
-        })
+            let parsed = match word {
+                "if" => {
+                    let (consequent, (end, word)) = Word::parse_until(input, dictionary, &["else", "then"], index)?;
+                    if word == "else" {
+                        let (alternative, _) = Word::parse_until(input, dictionary, &["then"], index)?;
+                        Word::If(consequent, alternative, Some(end))
+                    } else {
+                        Word::If(consequent, vec![], None)
+                    }
+                },
+                "begin" => {
+                    let (body, (end, _)) = Word::parse_until(input, dictionary, &["until"], index)?;
+                    Word::BeginUntil(body, end)
+                },
+                "do" => {
+                    let (body, (end, _)) = Word::parse_until(input, dictionary, &["loop"], index)?;
+                    Word::DoLoop(body, end)
+                },
+                _ if is_control(word) => return Err((Error::UnmatchedControl, index)),
+                _ if is_defining(word) => return Err((Error::InvalidWord, index)),
+                ".\"" => match &token.literal {
+                    Some(text) => Word::Print(Rc::from(text.as_str())),
+                    None => return Err((Error::InvalidWord, index)),
+                },
+                _ => match dictionary.get(word) {
+                    Some(address) => Word::Call(*address),
+                    None => word.parse::<Word<C>>().map_err(|e| (e, index))?,
+                },
+            };
+            words.push((index, parsed));
+        }
+        Ok((words, None))
+    }
+
+    /// Like `parse_block`, but fails at the `opening` token if no terminator is found.
+    fn parse_until<'a>(input: &mut impl Iterator<Item=(usize, &'a Token<'a>)>, dictionary: &Dictionary, terminators: &[&str], opening: usize) -> Result<(Words<C>, (usize, &'a str)), Located> {
+        match Word::parse_block(input, dictionary, terminators)? {
+            (words, Some(end)) => Ok((words, end)),
+            (_, None) => Err((Error::UnterminatedControl, opening)),
+        }
+    }
+
+    /// Appends the instructions for `words` to `code`, resolving control structures into jumps.
+    fn compile(words: &[(usize, Word<C>)], tokens: &[Token], code: &mut Code<C>) {
+        for (index, word) in words {
+            let index = *index;
+            let op_at = |instruction, index: usize| Op::new(instruction, index, &tokens[index].text);
+            let op = |instruction| op_at(instruction, index);
+            match word {
+                Word::Number(v) => code.push(op(Instruction::Push(v.clone()))),
+                Word::Operator(o) => code.push(op(Instruction::Operate(*o))),
+                Word::Unary(o) => code.push(op(Instruction::Unary(*o))),
+                Word::DivMod => code.push(op(Instruction::DivMod)),
+                Word::Memory(o) => code.push(op(Instruction::Memory(*o))),
+                Word::Output(o) => code.push(op(Instruction::Output(*o))),
+                Word::Print(text) => code.push(op(Instruction::Print(text.clone()))),
+                Word::Dup => code.push(op(Instruction::Dup)),
+                Word::Drop => code.push(op(Instruction::Drop)),
+                Word::Swap => code.push(op(Instruction::Swap)),
+                Word::Over => code.push(op(Instruction::Over)),
+                Word::LoopIndex => code.push(op(Instruction::LoopIndex)),
+                Word::Call(address) => code.push(op(Instruction::Call(*address))),
+                Word::If(consequent, alternative, otherwise) => {
+                    let branch = code.len();
+                    code.push(op(Instruction::JumpIfFalse(0)));
+                    Word::compile(consequent, tokens, code);
+                    if let Some(otherwise) = otherwise {
+                        let skip = code.len();
+                        code.push(op_at(Instruction::Jump(0), *otherwise));
+                        code[branch].instruction = Instruction::JumpIfFalse(code.len());
+                        Word::compile(alternative, tokens, code);
+                        code[skip].instruction = Instruction::Jump(code.len());
+                    } else {
+                        code[branch].instruction = Instruction::JumpIfFalse(code.len());
+                    }
+                },
+                Word::BeginUntil(body, until) => {
+                    let start = code.len();
+                    Word::compile(body, tokens, code);
+                    code.push(op_at(Instruction::JumpIfFalse(start), *until));
+                },
+                Word::DoLoop(body, end) => {
+                    let setup = code.len();
+                    code.push(op(Instruction::Do(0)));
+                    Word::compile(body, tokens, code);
+                    code.push(op_at(Instruction::Loop(setup + 1), *end));
+                    code[setup].instruction = Instruction::Do(code.len());
+                },
+            }
+        }
     }
 }
 
-impl FromStr for Word {
+impl<C: Cell> FromStr for Word<C> {
     type Err = Error;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> { 
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "dup" => Ok(Word::Dup),
             "drop" => Ok(Word::Drop),
             "swap" => Ok(Word::Swap),
             "over" => Ok(Word::Over),
+            "/mod" => Ok(Word::DivMod),
+            "i" => Ok(Word::LoopIndex),
             _ => {
-                if let Ok(v) = s.parse::<i32>() {
+                if let Ok(v) = s.parse::<C>() {
                     Ok(Word::Number(v))
                 } else if let Ok(o) = s.parse::<Operator>() {
                     Ok(Word::Operator(o))
+                } else if let Ok(o) = s.parse::<UnaryOperator>() {
+                    Ok(Word::Unary(o))
+                } else if let Ok(o) = s.parse::<MemoryOperator>() {
+                    Ok(Word::Memory(o))
+                } else if let Ok(o) = s.parse::<OutputOperator>() {
+                    Ok(Word::Output(o))
                 } else {
                     Err(Error::UnknownWord)
                 }
@@ -66,6 +400,12 @@ enum Operator {
     Minus,
     Mul,
     Div,
+    Mod,
+    Min,
+    Max,
+    Equal,
+    Less,
+    Greater,
 }
 
 impl FromStr for Operator {
@@ -77,131 +417,705 @@ impl FromStr for Operator {
             "-" => Ok(Operator::Minus),
             "*" => Ok(Operator::Mul),
             "/" => Ok(Operator::Div),
+            "mod" => Ok(Operator::Mod),
+            "min" => Ok(Operator::Min),
+            "max" => Ok(Operator::Max),
+            "=" => Ok(Operator::Equal),
+            "<" => Ok(Operator::Less),
+            ">" => Ok(Operator::Greater),
             _ => Err(Error::UnknownWord),
         }
     }
 }
 
-pub struct Forth {
-    stack: Vec<Value>,
-    commands: HashMap<String, Vec<Word>>,
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum UnaryOperator {
+    ZeroEqual,
+    Negate,
+    Abs,
+}
+
+impl FromStr for UnaryOperator {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "0=" => Ok(UnaryOperator::ZeroEqual),
+            "negate" => Ok(UnaryOperator::Negate),
+            "abs" => Ok(UnaryOperator::Abs),
+            _ => Err(Error::UnknownWord),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum MemoryOperator {
+    Fetch,
+    Store,
+    AddStore,
+    Allot,
+    Here,
+    Cells,
+}
+
+impl FromStr for MemoryOperator {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "@" => Ok(MemoryOperator::Fetch),
+            "!" => Ok(MemoryOperator::Store),
+            "+!" => Ok(MemoryOperator::AddStore),
+            "allot" => Ok(MemoryOperator::Allot),
+            "here" => Ok(MemoryOperator::Here),
+            "cells" => Ok(MemoryOperator::Cells),
+            _ => Err(Error::UnknownWord),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum OutputOperator {
+    /// `.` prints a number followed by a space
+    Dot,
+    Emit,
+    Cr,
+}
+
+impl FromStr for OutputOperator {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "." => Ok(OutputOperator::Dot),
+            "emit" => Ok(OutputOperator::Emit),
+            "cr" => Ok(OutputOperator::Cr),
+            _ => Err(Error::UnknownWord),
+        }
+    }
+}
+
+/// A single step of compiled code. Addresses are indices into `Forth::code`.
+#[derive(Debug, Clone, PartialEq)]
+enum Instruction<C> {
+    Push(C),
+    Operate(Operator),
+    Unary(UnaryOperator),
+    /// Pushes both the remainder and the quotient of a division
+    DivMod,
+    Memory(MemoryOperator),
+    Output(OutputOperator),
+    Print(Rc<str>),
+    Dup,
+    Drop,
+    Swap,
+    Over,
+    Jump(usize),
+    /// Pops a flag and jumps if it is false
+    JumpIfFalse(usize),
+    /// Pops the start index and limit of a loop, jumping past the loop if the range is empty
+    Do(usize),
+    /// Advances the innermost loop index, jumping back to the loop body until the limit is reached
+    Loop(usize),
+    LoopIndex,
+    Call(usize),
+    /// Runs the native word with the given index in `Forth::natives`
+    Native(usize),
+    Return,
+}
+
+type Code<C> = Vec<Op<C>>;
+
+/// A compiled instruction, with the index of the token it was compiled from and its word.
+#[derive(Debug, Clone)]
+struct Op<C> {
+    instruction: Instruction<C>,
+    token: usize,
+    word: Rc<str>,
+}
+
+impl<C> Op<C> {
+    fn new(instruction: Instruction<C>, token: usize, word: &str) -> Self {
+        Op { instruction, token, word: Rc::from(word) }
+    }
+}
+
+/// A saved interpreter state that `Forth::rollback` can return to.
+///
+/// The compiled code is saved as a whole rather than as a length: after rolling back to an
+/// earlier checkpoint, new words may be compiled over the code a later checkpoint refers to.
+#[derive(Debug, Clone)]
+pub struct Checkpoint<C> {
+    stack: Vec<C>,
+    memory: Vec<C>,
+    code: Code<C>,
+    natives: Vec<Native<C>>,
+    dictionary: Dictionary,
+    names: HashMap<usize, String>,
+}
+
+/// A Forth interpreter whose stack holds cells of type `C` and which prints to `W`.
+///
+/// `Forth::new` uses `i32` cells and captures output in a buffer that can be read with
+/// `Forth::output`. Other cell types are created with `Forth::<C>::default()`, and other
+/// output sinks with `Forth::with_output`.
+pub struct Forth<C: Cell = Value, W: Write = Vec<u8>> {
+    stack: Vec<C>,
+    /// Cell addressed memory used by `variable`, `allot`, `@` and `!`
+    memory: Vec<C>,
+    code: Code<C>,
+    natives: Vec<Native<C>>,
+    dictionary: Dictionary,
+    /// Names of user words by code address, kept for words that have since been redefined
+    names: HashMap<usize, String>,
+    /// Return addresses paired with the address of the word that was called
+    return_stack: Vec<(usize, usize)>,
+    loop_stack: Vec<(C, C)>,
+    transactional: bool,
+    limits: Limits,
+    /// Instructions executed for the current line
+    steps: usize,
+    trace: Option<TraceHook<C>>,
+    /// Names and stacks of the traced words that have been called but have not yet returned
+    trace_calls: Vec<(Rc<str>, Vec<C>)>,
+    output: W,
 }
 
 impl Forth {
     pub fn new() -> Forth {
+        Forth::default()
+    }
+}
+
+impl<C: Cell> Default for Forth<C> {
+    fn default() -> Self {
+        Forth::with_output(vec![])
+    }
+}
+
+impl<C: Cell> Forth<C> {
+    /// Text printed so far. Output is not undone by `rollback`.
+    pub fn output(&self) -> String {
+        String::from_utf8_lossy(&self.output).into_owned()
+    }
+}
+
+impl<C: Cell, W: Write> Forth<C, W> {
+    pub fn with_output(output: W) -> Self {
         Forth {
             stack: vec![],
-            commands: HashMap::default(),
+            memory: vec![],
+            code: vec![],
+            natives: vec![],
+            dictionary: HashMap::default(),
+            names: HashMap::default(),
+            return_stack: vec![],
+            loop_stack: vec![],
+            transactional: false,
+            limits: Limits::default(),
+            steps: 0,
+            trace: None,
+            trace_calls: vec![],
+            output,
         }
     }
 
-    pub fn stack(&self) -> Vec<Value> {
+    pub fn output_mut(&mut self) -> &mut W {
+        &mut self.output
+    }
+
+    pub fn into_output(self) -> W {
+        self.output
+    }
+
+    /// When enabled, a line that fails leaves the stack, memory and the dictionary exactly as they
+    /// were before it was evaluated.
+    pub fn set_transactional(&mut self, transactional: bool) {
+        self.transactional = transactional;
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Calls `hook` after every executed word with the stack before and after it. A user or
+    /// native word is reported once it returns, after the words it ran.
+    pub fn set_trace<F: FnMut(&TraceStep<'_, C>) + 'static>(&mut self, hook: F) {
+        self.trace = Some(Box::new(hook));
+    }
+
+    pub fn clear_trace(&mut self) {
+        self.trace = None;
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint<C> {
+        Checkpoint {
+            stack: self.stack.clone(),
+            memory: self.memory.clone(),
+            code: self.code.clone(),
+            natives: self.natives.clone(),
+            dictionary: self.dictionary.clone(),
+            names: self.names.clone(),
+        }
+    }
+
+    /// Restores the stack, memory and the dictionary saved in `checkpoint`, discarding any words
+    /// defined after it was taken.
+    pub fn rollback(&mut self, checkpoint: Checkpoint<C>) {
+        self.stack = checkpoint.stack;
+        self.memory = checkpoint.memory;
+        self.code = checkpoint.code;
+        self.natives = checkpoint.natives;
+        self.dictionary = checkpoint.dictionary;
+        self.names = checkpoint.names;
+    }
+
+    /// Defines `name` as a word implemented by `func`, which is only called when at least
+    /// `arity` values are on the stack.
+    ///
+    /// Like words defined with `: name ... ;`, a native word can be redefined, and words
+    /// defined before that keep using the old definition.
+    pub fn register_native<F>(&mut self, name: &str, arity: usize, func: F) -> ForthResult
+    where
+        F: Fn(&mut Stack<C>) -> ForthResult + 'static,
+    {
+        let name = name.to_lowercase();
+        if name.is_empty() || name.contains(char::is_whitespace) || !Self::is_valid_name(&name) {
+            return Err(Error::InvalidWord);
+        }
+        self.check_dictionary_size()?;
+        let address = self.code.len();
+        self.code.push(Op::new(Instruction::Native(self.natives.len()), 0, &name));
+        self.code.push(Op::new(Instruction::Return, 0, &name));
+        self.natives.push(Native { arity, func: Rc::new(func) });
+        self.define(&name, address);
+        Ok(())
+    }
+
+    pub fn stack(&self) -> Vec<C> {
         self.stack.clone()
     }
 
-    fn operate(&mut self, operator: &Operator) -> Result<Value, Error> {
-        let b = self.stack.pop().ok_or(Error::StackUnderflow)?;
-        let a = self.stack.pop().ok_or(Error::StackUnderflow)?;
+    fn pop(&mut self) -> Result<C, Error> {
+        self.stack.pop().ok_or(Error::StackUnderflow)
+    }
+
+    fn divisor(&mut self) -> Result<C, Error> {
+        let b = self.pop()?;
+        if b == C::zero() {
+            Err(Error::DivisionByZero)
+        } else {
+            Ok(b)
+        }
+    }
+
+    fn operate(&mut self, operator: Operator) -> Result<C, Error> {
+        let b = match operator {
+            Operator::Div | Operator::Mod => self.divisor()?,
+            _ => self.pop()?,
+        };
+        let a = self.pop()?;
+        match operator {
+            Operator::Plus => a.checked_add(&b).ok_or(Error::Overflow),
+            Operator::Minus => a.checked_sub(&b).ok_or(Error::Overflow),
+            Operator::Mul => a.checked_mul(&b).ok_or(Error::Overflow),
+            Operator::Div => a.checked_div(&b).ok_or(Error::Overflow),
+            Operator::Mod => a.checked_rem(&b).ok_or(Error::Overflow),
+            Operator::Min => Ok(a.min(b)),
+            Operator::Max => Ok(a.max(b)),
+            Operator::Equal => Ok(C::flag(a == b)),
+            Operator::Less => Ok(C::flag(a < b)),
+            Operator::Greater => Ok(C::flag(a > b)),
+        }
+    }
+
+    fn pop_address(&mut self) -> Result<usize, Error> {
+        let address = self.pop()?;
+        address
+            .to_usize()
+            .filter(|address| *address < self.memory.len())
+            .ok_or(Error::InvalidAddress)
+    }
+
+    /// Checks that memory may grow to `len` cells.
+    fn check_memory_size(&self, len: usize) -> Result<(), Error> {
+        if len > MAX_MEMORY_CELLS {
+            Err(Error::InvalidAddress)
+        } else if exceeds(self.limits.max_memory_cells, len) {
+            Err(Error::LimitExceeded(Limit::MemoryCells))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn access_memory(&mut self, operator: MemoryOperator) -> ForthResult {
         match operator {
-            Operator::Plus => Ok(a + b),
-            Operator::Minus => Ok(a - b),
-            Operator::Mul => Ok(a * b),
-            Operator::Div => {
-                if b == 0 {
-                    Err(Error::DivisionByZero)
+            MemoryOperator::Fetch => {
+                let address = self.pop_address()?;
+                self.stack.push(self.memory[address].clone());
+            },
+            MemoryOperator::Store => {
+                let address = self.pop_address()?;
+                self.memory[address] = self.pop()?;
+            },
+            MemoryOperator::AddStore => {
+                let address = self.pop_address()?;
+                let n = self.pop()?;
+                self.memory[address] = self.memory[address].checked_add(&n).ok_or(Error::Overflow)?;
+            },
+            MemoryOperator::Allot => {
+                let n = self.pop()?;
+                let len = if n < C::zero() {
+                    let released = n.checked_neg().and_then(|n| n.to_usize()).ok_or(Error::InvalidAddress)?;
+                    self.memory.len().checked_sub(released).ok_or(Error::InvalidAddress)?
                 } else {
-                    Ok(a / b)
-                }
-            }
+                    let allotted = n.to_usize().ok_or(Error::InvalidAddress)?;
+                    let len = self.memory.len().checked_add(allotted).ok_or(Error::InvalidAddress)?;
+                    self.check_memory_size(len)?;
+                    len
+                };
+                self.memory.resize(len, C::zero());
+            },
+            MemoryOperator::Here => {
+                let here = C::from_usize(self.memory.len()).ok_or(Error::Overflow)?;
+                self.stack.push(here);
+            },
+            // memory is addressed in whole cells, so a number of cells is already a size
+            MemoryOperator::Cells => {
+                let n = self.pop()?;
+                self.stack.push(n);
+            },
         }
+        Ok(())
     }
 
-    fn execute(&mut self, words: &[Word]) -> ForthResult {
-        for word in words {
-            match word {
-                Word::Number(v) => self.stack.push(*v),
-                Word::Operator(o) => {
-                    let res = self.operate(o)?;
-                    self.stack.push(res);
-                },
-                Word::Drop => {
-                    self.stack.pop().ok_or(Error::StackUnderflow)?;
+    fn write_output(&mut self, operator: OutputOperator) -> ForthResult {
+        let written = match operator {
+            OutputOperator::Dot => {
+                let a = self.pop()?;
+                write!(self.output, "{} ", a)
+            },
+            OutputOperator::Emit => {
+                let a = self.pop()?;
+                let c = a
+                    .to_usize()
+                    .and_then(|c| u32::try_from(c).ok())
+                    .and_then(std::char::from_u32)
+                    .ok_or(Error::InvalidCharacter)?;
+                write!(self.output, "{}", c)
+            },
+            OutputOperator::Cr => writeln!(self.output),
+        };
+        written.map_err(|_| Error::Output)
+    }
+
+    fn operate_unary(&mut self, operator: UnaryOperator) -> Result<C, Error> {
+        let a = self.pop()?;
+        match operator {
+            UnaryOperator::ZeroEqual => Ok(C::flag(a == C::zero())),
+            UnaryOperator::Negate => a.checked_neg().ok_or(Error::Overflow),
+            UnaryOperator::Abs if a < C::zero() => a.checked_neg().ok_or(Error::Overflow),
+            UnaryOperator::Abs => Ok(a),
+        }
+    }
+
+    /// Runs code from `address` until it returns from the outermost call.
+    ///
+    /// Errors are located at the token of the outermost instruction being executed.
+    fn execute(&mut self, address: usize) -> Result<(), Located> {
+        let mut pc = address;
+        loop {
+            match self.traced_step(&mut pc) {
+                Ok(true) => return Ok(()),
+                Ok(false) => {},
+                Err(e) => {
+                    let origin = self.return_stack.first().map(|(ret, _)| *ret).unwrap_or(pc) - 1;
+                    return Err((e, self.code[origin].token));
                 },
-                Word::Dup => {
-                    if self.stack.len() < 1 {
-                        return Err(Error::StackUnderflow);
+            }
+        }
+    }
+
+    /// Runs `step`, checking the stack depth afterwards and reporting the step to the trace hook.
+    fn traced_step(&mut self, pc: &mut usize) -> Result<bool, Error> {
+        let address = *pc;
+        let depth = self.return_stack.len();
+        let before = self.trace.as_ref().map(|_| self.stack.clone());
+        let finished = self.step(pc)?;
+        if exceeds(self.limits.max_stack_depth, self.stack.len()) {
+            return Err(Error::LimitExceeded(Limit::StackDepth));
+        }
+        if let (Some(before), Some(trace)) = (before, self.trace.as_mut()) {
+            let op = &self.code[address];
+            match op.instruction {
+                // a called word is reported when it returns, so that `after` is the stack it left
+                Instruction::Call(_) => self.trace_calls.push((Rc::clone(&op.word), before)),
+                Instruction::Return if !finished => {
+                    if let Some((word, before)) = self.trace_calls.pop() {
+                        trace(&TraceStep {
+                            word: &word,
+                            depth: self.return_stack.len(),
+                            before: &before,
+                            after: &self.stack,
+                        });
                     }
-                    self.stack.push(self.stack[self.stack.len() - 1])
-                },
-                Word::Swap => {
-                    let a = self.stack.pop().ok_or(Error::StackUnderflow)?;
-                    let b = self.stack.pop().ok_or(Error::StackUnderflow)?;
-                    self.stack.push(a);
-                    self.stack.push(b);
-                },
-                Word::Over => {
-                    let a = self.stack.pop().ok_or(Error::StackUnderflow)?;
-                    let b = self.stack.pop().ok_or(Error::StackUnderflow)?;
-                    self.stack.push(b);
-                    self.stack.push(a);
-                    self.stack.push(b);
-                },
-                Word::Command(com) => {
-                    let ws = match self.commands.remove(com) {
-                        Some(ws) => ws,
-                        _ => return Err(Error::InvalidWord),
-
-                    };
-                    self.execute(&ws)?;
-                    self.commands.insert(com.to_string(), ws);
-                },
+                }
+                // other returns and jumps are bookkeeping rather than words of the program
+                Instruction::Return | Instruction::Jump(_) => {}
+                _ => trace(&TraceStep {
+                    word: &op.word,
+                    depth,
+                    before: &before,
+                    after: &self.stack,
+                }),
             }
         }
+        Ok(finished)
+    }
+
+    /// Executes the instruction at `pc`, returning whether the outermost call has returned.
+    fn step(&mut self, pc: &mut usize) -> Result<bool, Error> {
+        let instruction = self.code[*pc].instruction.clone();
+        *pc += 1;
+        self.steps += 1;
+        if exceeds(self.limits.max_steps, self.steps) {
+            return Err(Error::LimitExceeded(Limit::Steps));
+        }
+        match instruction {
+            Instruction::Push(v) => self.stack.push(v),
+            Instruction::Operate(o) => {
+                let res = self.operate(o)?;
+                self.stack.push(res);
+            },
+            Instruction::Unary(o) => {
+                let res = self.operate_unary(o)?;
+                self.stack.push(res);
+            },
+            Instruction::Memory(o) => self.access_memory(o)?,
+            Instruction::Output(o) => self.write_output(o)?,
+            Instruction::Print(text) => self.output.write_all(text.as_bytes()).map_err(|_| Error::Output)?,
+            Instruction::DivMod => {
+                let b = self.divisor()?;
+                let a = self.pop()?;
+                let remainder = a.checked_rem(&b).ok_or(Error::Overflow)?;
+                let quotient = a.checked_div(&b).ok_or(Error::Overflow)?;
+                self.stack.push(remainder);
+                self.stack.push(quotient);
+            },
+            Instruction::Drop => {
+                self.pop()?;
+            },
+            Instruction::Dup => {
+                let a = self.stack.last().ok_or(Error::StackUnderflow)?.clone();
+                self.stack.push(a)
+            },
+            Instruction::Swap => {
+                let a = self.pop()?;
+                let b = self.pop()?;
+                self.stack.push(a);
+                self.stack.push(b);
+            },
+            Instruction::Over => {
+                let a = self.pop()?;
+                let b = self.pop()?;
+                self.stack.push(b.clone());
+                self.stack.push(a);
+                self.stack.push(b);
+            },
+            Instruction::Jump(target) => *pc = target,
+            Instruction::JumpIfFalse(target) => {
+                if self.pop()? == C::zero() {
+                    *pc = target;
+                }
+            },
+            Instruction::Do(end) => {
+                let start = self.pop()?;
+                let limit = self.pop()?;
+                if start < limit {
+                    self.loop_stack.push((start, limit));
+                } else {
+                    *pc = end;
+                }
+            },
+            Instruction::Loop(body) => {
+                let (index, limit) = self.loop_stack.last_mut().ok_or(Error::UnmatchedControl)?;
+                *index = index.checked_add(&C::one()).ok_or(Error::Overflow)?;
+                if index < limit {
+                    *pc = body;
+                } else {
+                    self.loop_stack.pop();
+                }
+            },
+            Instruction::LoopIndex => {
+                let (index, _) = self.loop_stack.last().ok_or(Error::UnmatchedControl)?;
+                self.stack.push(index.clone());
+            },
+            Instruction::Call(target) => {
+                if exceeds(self.limits.max_recursion_depth, self.return_stack.len() + 1) {
+                    return Err(Error::LimitExceeded(Limit::RecursionDepth));
+                }
+                self.return_stack.push((*pc, target));
+                *pc = target;
+            },
+            Instruction::Native(index) => {
+                let native = &self.natives[index];
+                if self.stack.len() < native.arity {
+                    return Err(Error::StackUnderflow);
+                }
+                let func = native.func.clone();
+                func(&mut self.stack)?;
+            },
+            Instruction::Return => match self.return_stack.pop() {
+                Some((address, _)) => *pc = address,
+                None => return Ok(true),
+            },
+        }
+        Ok(false)
+    }
+
+    fn handle_new_command(&mut self, tokens: &[Token]) -> Result<(), Located> {
+        let last = tokens.len() - 1;
+        if last < 2 || tokens[last].text != ";" {
+            return Err((Error::InvalidWord, last));
+        }
+        let name = self.definition_name(tokens, 1)?;
+        self.check_dictionary_size().map_err(|e| (e, 1))?;
+        let mut body = tokens[2..last]
+            .iter()
+            .enumerate()
+            .map(|(i, token)| (i + 2, token));
+        let words = Word::parse_string(&mut body, &self.dictionary)?;
+        let address = self.code.len();
+        Word::compile(&words, tokens, &mut self.code);
+        self.code.push(Op::new(Instruction::Return, last, ";"));
+        self.define(name, address);
         Ok(())
     }
 
-    fn handle_new_command(&mut self, input: &str) -> ForthResult {
-        if input.ends_with(" ;") {
-            let mut inputs = input.split(" ").collect::<Vec<&str>>();
-            inputs.remove(0);
-            inputs.pop();
-            let name = inputs.remove(0);
-            if name.parse::<Value>().is_ok() {
-                return Err(Error::InvalidWord);
-            }
-            let words = Word::parse_string(&mut inputs.into_iter(), &self.commands)?
-                    .into_iter()
-                    .fold(vec![], |mut acc, w| {
-                        match w {
-                            Word::Command(c) => {
-                                acc.append(&mut self.commands
-                                           .get(&c)
-                                           .expect("Word parsing should fail if command does not exsist").clone());
-                            },
-                            _ => acc.push(w)
-                        }
-                        acc
-                    });
-            self.commands.insert(name.to_string(), words);
-            Ok(())
+    fn is_valid_name(name: &str) -> bool {
+        name.parse::<C>().is_err() && !is_control(name) && !is_defining(name)
+    }
+
+    fn definition_name<'a>(&self, tokens: &'a [Token], index: usize) -> Result<&'a str, Located> {
+        let name = &tokens[index].text;
+        if Self::is_valid_name(name) {
+            Ok(name)
         } else {
-            Err(Error::InvalidWord)
+            Err((Error::InvalidWord, index))
         }
     }
 
-    pub fn eval(&mut self, input: &str) -> ForthResult {
-        if input.len() == 0 {
+    /// Checks that the dictionary has room for one more word.
+    fn check_dictionary_size(&self) -> Result<(), Error> {
+        if exceeds(self.limits.max_dictionary_size, self.names.len() + 1) {
+            Err(Error::LimitExceeded(Limit::DictionarySize))
+        } else {
             Ok(())
+        }
+    }
+
+    fn define(&mut self, name: &str, address: usize) {
+        self.dictionary.insert(name.to_string(), address);
+        self.names.insert(address, name.to_string());
+    }
+
+    /// Handles `variable name` and `constant name`, where `index` is the position of the
+    /// defining word. Both define a word that pushes a single value.
+    fn handle_data_word(&mut self, tokens: &[Token], index: usize) -> Result<(), Located> {
+        if index + 1 >= tokens.len() {
+            return Err((Error::InvalidWord, index));
+        }
+        let name = self.definition_name(tokens, index + 1)?;
+        self.check_dictionary_size().map_err(|e| (e, index + 1))?;
+        let value = if tokens[index].text == "variable" {
+            self.check_memory_size(self.memory.len() + 1).map_err(|e| (e, index))?;
+            let address = C::from_usize(self.memory.len()).ok_or((Error::Overflow, index))?;
+            self.memory.push(C::zero());
+            address
         } else {
-            let input = input.to_lowercase();
-            if input.starts_with(":") {
-                self.handle_new_command(&input)?;
-            } else {
-                let words = Word::parse_string(&mut input.split(" "), &self.commands)?;
-                self.execute(&words)?;
-            }
+            self.pop().map_err(|e| (e, index))?
+        };
+        let address = self.code.len();
+        self.code.push(Op::new(Instruction::Push(value), index + 1, name));
+        self.code.push(Op::new(Instruction::Return, index + 1, name));
+        self.define(name, address);
+        Ok(())
+    }
 
-            Ok(())
+    fn run_line(&mut self, tokens: &[Token]) -> Result<(), Located> {
+        if tokens[0].text == ":" {
+            return self.handle_new_command(tokens);
+        }
+        // defining words split the line, so that the words after them can use the new word
+        let mut start = 0;
+        while let Some(offset) = tokens[start..].iter().position(|token| is_defining(&token.text)) {
+            let index = start + offset;
+            self.run_words(tokens, start, index)?;
+            self.handle_data_word(tokens, index)?;
+            start = index + 2;
+        }
+        self.run_words(tokens, start, tokens.len())
+    }
+
+    /// Parses, compiles and runs `tokens[start..end]`.
+    fn run_words(&mut self, tokens: &[Token], start: usize, end: usize) -> Result<(), Located> {
+        if start >= end {
+            return Ok(());
+        }
+        let mut words = tokens[start..end]
+            .iter()
+            .enumerate()
+            .map(|(i, token)| (start + i, token));
+        let words = Word::parse_string(&mut words, &self.dictionary)?;
+        // top level code is compiled past the end of the dictionary and discarded afterwards
+        let address = self.code.len();
+        Word::compile(&words, tokens, &mut self.code);
+        self.code.push(Op::new(Instruction::Return, end - 1, &tokens[end - 1].text));
+        let res = self.execute(address);
+        self.code.truncate(address);
+        res
+    }
+
+    /// Evaluates a line of input, reporting which word caused an error.
+    pub fn try_eval(&mut self, input: &str) -> Result<(), ForthError> {
+        let tokens = tokenize(input);
+        if tokens.is_empty() {
+            return Ok(());
         }
+        self.return_stack.clear();
+        self.trace_calls.clear();
+        self.steps = 0;
+        self.loop_stack.clear();
+        let checkpoint = if self.transactional {
+            Some(self.checkpoint())
+        } else {
+            None
+        };
+        self.run_line(&tokens).map_err(|(kind, index)| {
+            let token = &tokens[index];
+            let error = ForthError {
+                kind,
+                word: token.source.to_string(),
+                offset: token.offset,
+                column: input[..token.offset].chars().count() + 1,
+                definition: match tokens.get(1) {
+                    Some(name) if tokens[0].text == ":" => Some(name.source.to_string()),
+                    _ => None,
+                },
+                call_stack: self
+                    .return_stack
+                    .iter()
+                    .map(|(_, word)| self.names[word].clone())
+                    .collect(),
+            };
+            if let Some(checkpoint) = checkpoint {
+                self.rollback(checkpoint);
+            }
+            error
+        })
+    }
+
+    pub fn eval(&mut self, input: &str) -> ForthResult {
+        self.try_eval(input).map_err(|e| e.kind)
     }
 }
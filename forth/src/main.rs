@@ -0,0 +1,44 @@
+use forth::{Forth, Value};
+use std::{
+    env,
+    fs::File,
+    io::{self, BufRead, BufReader, Write},
+};
+
+fn format_stack(stack: &[Value]) -> String {
+    stack
+        .iter()
+        .map(|v| v.to_string())
+        .fold(format!("<{}>", stack.len()), |acc, v| acc + " " + &v)
+}
+
+/// Reads Forth from the file given as the first argument, or from stdin, one line at a time.
+///
+/// After every line the text it printed and the current stack are shown. A line that fails is reported and undone, so
+/// the interpreter continues from the state it had before that line.
+fn main() -> io::Result<()> {
+    let input: Box<dyn BufRead> = match env::args().nth(1) {
+        Some(path) => Box::new(BufReader::new(File::open(path)?)),
+        None => Box::new(BufReader::new(io::stdin())),
+    };
+    let stdout = io::stdout();
+    let mut output = stdout.lock();
+    let mut forth = Forth::new();
+    forth.set_transactional(true);
+
+    for (number, line) in input.lines().enumerate() {
+        let line = line?;
+        let result = forth.try_eval(line.trim_end());
+        let printed = forth.output_mut().split_off(0);
+        output.write_all(&printed)?;
+        if !printed.is_empty() && !printed.ends_with(b"\n") {
+            writeln!(output)?;
+        }
+        match result {
+            Ok(()) => writeln!(output, "ok {}", format_stack(&forth.stack()))?,
+            Err(e) => writeln!(output, "error on line {}: {}", number + 1, e)?,
+        }
+        output.flush()?;
+    }
+    Ok(())
+}
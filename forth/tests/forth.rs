@@ -1,4 +1,4 @@
-use forth::{Error, Forth, Value};
+use forth::{Error, Forth, ForthError, Limit, Limits, Stack, Value, MAX_MEMORY_CELLS};
 
 #[test]
 fn no_input_no_stack() {
@@ -302,3 +302,734 @@ fn calling_non_existing_word() {
     let mut f = Forth::new();
     assert_eq!(Err(Error::UnknownWord), f.eval("1 foo"));
 }
+
+// Control flow
+
+#[test]
+fn comparison_words_push_forth_flags() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 1 = 1 2 = 1 2 < 2 1 < 2 1 > 1 2 >").is_ok());
+    assert_eq!(vec![-1, 0, -1, 0, -1, 0], f.stack());
+}
+
+#[test]
+fn zero_equals() {
+    let mut f = Forth::new();
+    assert!(f.eval("0 0= 5 0=").is_ok());
+    assert_eq!(vec![-1, 0], f.stack());
+}
+
+#[test]
+fn comparison_error() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::StackUnderflow), f.eval("1 ="));
+    assert_eq!(Err(Error::StackUnderflow), f.eval("0="));
+}
+
+#[test]
+fn if_then_at_top_level() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 -1 if 2 then 0 if 3 then").is_ok());
+    assert_eq!(vec![1, 2], f.stack());
+}
+
+#[test]
+fn if_else_then() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 if 10 else 20 then 0 IF 10 ELSE 20 THEN").is_ok());
+    assert_eq!(vec![10, 20], f.stack());
+}
+
+#[test]
+fn nested_if() {
+    let mut f = Forth::new();
+    assert!(f.eval(": sign dup 0 < if drop -1 else 0 > if 1 else 0 then then ;").is_ok());
+    assert!(f.eval("-5 sign 0 sign 7 sign").is_ok());
+    assert_eq!(vec![-1, 0, 1], f.stack());
+}
+
+#[test]
+fn if_error() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::StackUnderflow), f.eval("if 1 then"));
+}
+
+#[test]
+fn begin_until() {
+    let mut f = Forth::new();
+    assert!(f.eval(": countdown begin dup 1 - dup 0= until ;").is_ok());
+    assert!(f.eval("3 countdown").is_ok());
+    assert_eq!(vec![3, 2, 1, 0], f.stack());
+}
+
+#[test]
+fn do_loop_with_index() {
+    let mut f = Forth::new();
+    assert!(f.eval("5 0 do i loop").is_ok());
+    assert_eq!(vec![0, 1, 2, 3, 4], f.stack());
+}
+
+#[test]
+fn do_loop_with_empty_range_is_skipped() {
+    let mut f = Forth::new();
+    assert!(f.eval("0 0 do i loop").is_ok());
+    assert_eq!(Vec::<Value>::new(), f.stack());
+}
+
+#[test]
+fn nested_do_loops_use_innermost_index() {
+    let mut f = Forth::new();
+    assert!(f.eval(": table 3 1 do 3 1 do i loop loop ;").is_ok());
+    assert!(f.eval("table").is_ok());
+    assert_eq!(vec![1, 2, 1, 2], f.stack());
+}
+
+#[test]
+fn control_flow_inside_definitions_uses_snapshot_of_words() {
+    let mut f = Forth::new();
+    assert!(f.eval(": step 2 ;").is_ok());
+    assert!(f.eval(": steps 3 0 do step loop ;").is_ok());
+    assert!(f.eval(": step 5 ;").is_ok());
+    assert!(f.eval("steps").is_ok());
+    assert_eq!(vec![2, 2, 2], f.stack());
+}
+
+#[test]
+fn unmatched_control_words() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::UnmatchedControl), f.eval("1 then"));
+    assert_eq!(Err(Error::UnmatchedControl), f.eval("1 else 2"));
+    assert_eq!(Err(Error::UnmatchedControl), f.eval("until"));
+    assert_eq!(Err(Error::UnmatchedControl), f.eval(": foo loop ;"));
+    assert_eq!(Err(Error::UnmatchedControl), f.eval("i"));
+}
+
+#[test]
+fn unterminated_control_structures() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::UnterminatedControl), f.eval("1 if 2"));
+    assert_eq!(Err(Error::UnterminatedControl), f.eval("1 if 2 else 3"));
+    assert_eq!(Err(Error::UnterminatedControl), f.eval("begin 1"));
+    assert_eq!(Err(Error::UnterminatedControl), f.eval(": foo 3 0 do i ;"));
+}
+
+#[test]
+fn control_words_cannot_be_redefined() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::InvalidWord), f.eval(": if 1 ;"));
+}
+
+// Compilation
+
+#[test]
+fn deeply_layered_definitions_do_not_grow_exponentially() {
+    let mut f = Forth::new();
+    assert!(f.eval(": w0 1 ;").is_ok());
+    for layer in 1..=64 {
+        let definition = format!(": w{} w{} w{} ;", layer, layer - 1, layer - 1);
+        assert!(f.eval(&definition).is_ok());
+    }
+    assert!(f.eval(": w1 2 ;").is_ok());
+    assert!(f.eval("w1 w2").is_ok());
+    assert_eq!(vec![2, 1, 1, 1, 1], f.stack());
+}
+
+#[test]
+fn words_can_call_words_containing_loops() {
+    let mut f = Forth::new();
+    assert!(f.eval(": push-index i ;").is_ok());
+    assert!(f.eval(": indices 3 0 do push-index loop ;").is_ok());
+    assert!(f.eval("indices indices").is_ok());
+    assert_eq!(vec![0, 1, 2, 0, 1, 2], f.stack());
+}
+
+// Error locations
+
+#[test]
+fn errors_are_located_at_the_offending_word() {
+    let mut f = Forth::new();
+    let error = f.try_eval("1 2 + FOO").unwrap_err();
+    assert_eq!(Error::UnknownWord, error.kind);
+    assert_eq!("FOO", error.word);
+    assert_eq!(7, error.column);
+}
+
+#[test]
+fn runtime_errors_inside_words_are_located_at_the_caller() {
+    let mut f = Forth::new();
+    assert!(f.eval(": divide / ;").is_ok());
+    let error = f.try_eval("1 2 divide 4 0 divide").unwrap_err();
+    assert_eq!(Error::DivisionByZero, error.kind);
+    assert_eq!("divide", error.word);
+    assert_eq!(16, error.column);
+}
+
+#[test]
+fn unterminated_control_is_located_at_the_opening_word() {
+    let mut f = Forth::new();
+    let error = f.try_eval("1 if 2").unwrap_err();
+    assert_eq!(Error::UnterminatedControl, error.kind);
+    assert_eq!(3, error.column);
+}
+
+// Transactional evaluation
+
+fn transactional() -> Forth {
+    let mut f = Forth::new();
+    f.set_transactional(true);
+    assert!(f.eval(": double 2 * ;").is_ok());
+    assert!(f.eval("1 2").is_ok());
+    f
+}
+
+#[test]
+fn non_transactional_eval_leaves_partial_stack() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::StackUnderflow), f.eval("1 2 + drop drop"));
+    assert_eq!(Vec::<Value>::new(), f.stack());
+}
+
+#[test]
+fn transactional_eval_keeps_successful_lines() {
+    let mut f = transactional();
+    assert!(f.eval("double").is_ok());
+    assert_eq!(vec![1, 4], f.stack());
+}
+
+#[test]
+fn transactional_rollback_on_division_by_zero() {
+    let mut f = transactional();
+    assert_eq!(Err(Error::DivisionByZero), f.eval("drop 5 0 /"));
+    assert_eq!(vec![1, 2], f.stack());
+}
+
+#[test]
+fn transactional_rollback_on_stack_underflow() {
+    let mut f = transactional();
+    assert_eq!(Err(Error::StackUnderflow), f.eval("+ drop drop"));
+    assert_eq!(vec![1, 2], f.stack());
+}
+
+#[test]
+fn transactional_rollback_on_unknown_word() {
+    let mut f = transactional();
+    assert_eq!(Err(Error::UnknownWord), f.eval("3 double foo"));
+    assert_eq!(vec![1, 2], f.stack());
+}
+
+#[test]
+fn transactional_rollback_on_invalid_word() {
+    let mut f = transactional();
+    assert_eq!(Err(Error::InvalidWord), f.eval(": double"));
+    assert!(f.eval("double").is_ok());
+    assert_eq!(vec![1, 4], f.stack());
+}
+
+#[test]
+fn transactional_rollback_on_unmatched_control() {
+    let mut f = transactional();
+    assert_eq!(Err(Error::UnmatchedControl), f.eval(": double dup then ;"));
+    assert_eq!(Err(Error::UnmatchedControl), f.eval("drop i"));
+    assert!(f.eval("double").is_ok());
+    assert_eq!(vec![1, 4], f.stack());
+}
+
+#[test]
+fn transactional_rollback_on_unterminated_control() {
+    let mut f = transactional();
+    assert_eq!(Err(Error::UnterminatedControl), f.eval("3 0 do i"));
+    assert_eq!(vec![1, 2], f.stack());
+}
+
+#[test]
+fn rollback_to_a_checkpoint_taken_before_an_earlier_rollback() {
+    let mut f = Forth::new();
+    let a = f.checkpoint();
+    assert!(f.eval(": foo 1 ;").is_ok());
+    let b = f.checkpoint();
+    f.rollback(a);
+    assert!(f.eval(": bar 2 3 4 5 ;").is_ok());
+    f.rollback(b);
+    assert!(f.eval("foo").is_ok());
+    assert_eq!(vec![1], f.stack());
+    assert_eq!(Err(Error::UnknownWord), f.eval("bar"));
+}
+
+#[test]
+fn rollback_restores_stack_and_dictionary() {
+    let mut f = Forth::new();
+    assert!(f.eval(": foo 1 ;").is_ok());
+    let checkpoint = f.checkpoint();
+    assert!(f.eval(": foo 2 ;").is_ok());
+    assert!(f.eval(": bar foo foo ;").is_ok());
+    assert!(f.eval("bar").is_ok());
+    assert_eq!(vec![2, 2], f.stack());
+    f.rollback(checkpoint);
+    assert_eq!(Vec::<Value>::new(), f.stack());
+    assert_eq!(Err(Error::UnknownWord), f.eval("bar"));
+    assert!(f.eval("foo").is_ok());
+    assert_eq!(vec![1], f.stack());
+}
+
+#[test]
+fn errors_carry_byte_offset_of_the_offending_word() {
+    let mut f = Forth::new();
+    let error = f.try_eval("1 ÷ 2").unwrap_err();
+    assert_eq!(Error::UnknownWord, error.kind);
+    assert_eq!(2, error.offset);
+    assert_eq!(3, error.column);
+}
+
+#[test]
+fn errors_name_the_definition_being_compiled() {
+    let mut f = Forth::new();
+    let error = f.try_eval(": Foo 1 bar ;").unwrap_err();
+    assert_eq!(Error::UnknownWord, error.kind);
+    assert_eq!("bar", error.word);
+    assert_eq!(Some("Foo".to_string()), error.definition);
+    assert_eq!(None, f.try_eval("1 bar").unwrap_err().definition);
+}
+
+#[test]
+fn errors_carry_the_call_stack_of_user_words() {
+    let mut f = Forth::new();
+    assert!(f.eval(": inner drop drop ;").is_ok());
+    assert!(f.eval(": outer 1 inner ;").is_ok());
+    assert!(f.eval(": inner 2 ;").is_ok());
+    let error = f.try_eval("outer").unwrap_err();
+    assert_eq!(Error::StackUnderflow, error.kind);
+    assert_eq!(vec!["outer".to_string(), "inner".to_string()], error.call_stack);
+    assert!(f.try_eval("drop").unwrap_err().call_stack.is_empty());
+}
+
+#[test]
+fn errors_display_their_context() {
+    let mut f = Forth::new();
+    assert!(f.eval(": zero 0 / ;").is_ok());
+    let error = f.try_eval("1 zero").unwrap_err();
+    assert_eq!("division by zero at column 3: zero (in zero)", error.to_string());
+    let error = f.try_eval(": bad foo ;").unwrap_err();
+    assert_eq!("unknown word at column 7: foo (while defining bad)", error.to_string());
+}
+
+#[test]
+fn rich_errors_convert_to_error_kinds() {
+    let mut f = Forth::new();
+    let error: ForthError = f.try_eval("+").unwrap_err();
+    let source = std::error::Error::source(&error).map(|e| e.to_string());
+    assert_eq!(Some("stack underflow".to_string()), source);
+    assert_eq!(Error::StackUnderflow, Error::from(error));
+}
+
+// Cell types and arithmetic words
+
+#[test]
+fn modulo() {
+    let mut f = Forth::new();
+    assert!(f.eval("13 4 mod -13 4 MOD").is_ok());
+    assert_eq!(vec![1, -1], f.stack());
+    assert_eq!(Err(Error::DivisionByZero), f.eval("1 0 mod"));
+}
+
+#[test]
+fn div_mod_pushes_remainder_then_quotient() {
+    let mut f = Forth::new();
+    assert!(f.eval("13 4 /mod").is_ok());
+    assert_eq!(vec![1, 3], f.stack());
+    assert_eq!(Err(Error::DivisionByZero), f.eval("1 0 /mod"));
+    assert_eq!(Err(Error::StackUnderflow), Forth::new().eval("1 /mod"));
+}
+
+#[test]
+fn negate_and_abs() {
+    let mut f = Forth::new();
+    assert!(f.eval("5 negate -5 negate -7 abs 7 abs").is_ok());
+    assert_eq!(vec![-5, 5, 7, 7], f.stack());
+}
+
+#[test]
+fn min_and_max() {
+    let mut f = Forth::new();
+    assert!(f.eval("3 -2 min 3 -2 max").is_ok());
+    assert_eq!(vec![-2, 3], f.stack());
+}
+
+#[test]
+fn overflow_is_an_error() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::Overflow), f.eval("2147483647 1 +"));
+    assert_eq!(Err(Error::Overflow), f.eval("-2147483648 1 -"));
+    assert_eq!(Err(Error::Overflow), f.eval("65536 65536 *"));
+    assert_eq!(Err(Error::Overflow), f.eval("-2147483648 -1 /"));
+    assert_eq!(Err(Error::Overflow), f.eval("-2147483648 negate"));
+    assert_eq!(Err(Error::Overflow), f.eval("-2147483648 abs"));
+}
+
+#[test]
+fn wider_cells_hold_larger_values() {
+    let mut f = Forth::<i64>::default();
+    assert!(f.eval("65536 65536 * dup 0 <").is_ok());
+    assert_eq!(vec![4_294_967_296, 0], f.stack());
+    assert_eq!(Err(Error::Overflow), f.eval("9223372036854775807 1 +"));
+
+    let mut f = Forth::<i128>::default();
+    assert!(f.eval("9223372036854775807 1 +").is_ok());
+    assert_eq!(vec![9_223_372_036_854_775_808], f.stack());
+}
+
+#[cfg(feature = "bigint")]
+#[test]
+fn big_integer_cells_never_overflow() {
+    use num_bigint::BigInt;
+
+    let mut f = Forth::<BigInt>::default();
+    assert!(f.eval(": square dup * ;").is_ok());
+    assert!(f.eval("4294967296 square square dup 0 <").is_ok());
+    let expected: BigInt = "340282366920938463463374607431768211456".parse().unwrap();
+    assert_eq!(vec![expected, BigInt::from(0)], f.stack());
+}
+
+// Memory
+
+#[test]
+fn variables_store_and_fetch_values() {
+    let mut f = Forth::new();
+    assert!(f.eval("variable x").is_ok());
+    assert!(f.eval("42 x !").is_ok());
+    assert!(f.eval("x @ x @").is_ok());
+    assert_eq!(vec![42, 42], f.stack());
+}
+
+#[test]
+fn variables_start_at_zero_and_persist_between_lines() {
+    let mut f = Forth::new();
+    assert!(f.eval("variable counter counter @").is_ok());
+    assert!(f.eval(": bump 1 counter +! ;").is_ok());
+    assert!(f.eval("bump bump bump").is_ok());
+    assert!(f.eval("counter @").is_ok());
+    assert_eq!(vec![0, 3], f.stack());
+}
+
+#[test]
+fn constants_push_their_value() {
+    let mut f = Forth::new();
+    assert!(f.eval("6 7 * constant answer answer").is_ok());
+    assert!(f.eval(": twice answer 2 * ;").is_ok());
+    assert!(f.eval("twice").is_ok());
+    assert_eq!(vec![42, 84], f.stack());
+}
+
+#[test]
+fn constant_needs_a_value() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::StackUnderflow), f.eval("constant nothing"));
+}
+
+#[test]
+fn defining_words_need_a_valid_name() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::InvalidWord), f.eval("variable"));
+    assert_eq!(Err(Error::InvalidWord), f.eval("variable 12"));
+    assert_eq!(Err(Error::InvalidWord), f.eval("1 constant if"));
+    assert_eq!(Err(Error::InvalidWord), f.eval(": variable 1 ;"));
+    assert_eq!(Err(Error::InvalidWord), f.eval(": foo variable x ;"));
+}
+
+#[test]
+fn here_allot_and_cells_reserve_arrays() {
+    let mut f = Forth::new();
+    assert!(f.eval("here 3 cells allot here").is_ok());
+    assert_eq!(vec![0, 3], f.stack());
+    assert!(f.eval("drop 7 over 2 + ! 2 + @").is_ok());
+    assert_eq!(vec![7], f.stack());
+}
+
+#[test]
+fn negative_allot_releases_memory() {
+    let mut f = Forth::new();
+    assert!(f.eval("4 allot -3 allot here").is_ok());
+    assert_eq!(vec![1], f.stack());
+    assert_eq!(Err(Error::InvalidAddress), f.eval("-2 allot"));
+}
+
+#[test]
+fn huge_allot_is_rejected() {
+    let mut f = Forth::<i64>::default();
+    assert_eq!(Err(Error::InvalidAddress), f.eval("9223372036854775807 allot"));
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::InvalidAddress), f.eval("2147483647 allot"));
+    assert!(f.eval(&format!("{} allot here", MAX_MEMORY_CELLS)).is_ok());
+    assert_eq!(vec![MAX_MEMORY_CELLS as Value], f.stack());
+    assert_eq!(Err(Error::InvalidAddress), f.eval("1 allot"));
+    assert_eq!(Err(Error::InvalidAddress), f.eval("variable x"));
+}
+
+#[test]
+fn memory_access_is_bounds_checked() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::InvalidAddress), f.eval("0 @"));
+    assert!(f.eval("variable x").is_ok());
+    assert_eq!(Err(Error::InvalidAddress), f.eval("1 1 !"));
+    assert_eq!(Err(Error::InvalidAddress), f.eval("1 -1 +!"));
+    assert_eq!(Err(Error::StackUnderflow), Forth::new().eval("@"));
+}
+
+#[test]
+fn transactional_rollback_restores_memory() {
+    let mut f = Forth::new();
+    f.set_transactional(true);
+    assert!(f.eval("variable x 5 x !").is_ok());
+    assert_eq!(Err(Error::InvalidAddress), f.eval("9 x ! 10 allot 1 100 !"));
+    assert!(f.eval("x @ here").is_ok());
+    assert_eq!(vec![5, 1], f.stack());
+}
+
+// Output
+
+#[test]
+fn dot_prints_and_pops_numbers() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 2 3 . .").is_ok());
+    assert_eq!("3 2 ", f.output());
+    assert_eq!(vec![1], f.stack());
+    assert_eq!(Err(Error::StackUnderflow), Forth::new().eval("."));
+}
+
+#[test]
+fn emit_and_cr() {
+    let mut f = Forth::new();
+    assert!(f.eval("72 emit 105 emit cr 955 EMIT").is_ok());
+    assert_eq!("Hi\nλ", f.output());
+    assert_eq!(Err(Error::InvalidCharacter), f.eval("-1 emit"));
+    assert_eq!(Err(Error::InvalidCharacter), f.eval("55296 emit"));
+}
+
+#[test]
+fn dot_quote_prints_text_verbatim() {
+    let mut f = Forth::new();
+    assert!(f.eval(": greet .\" Hello,  World!\" cr ;").is_ok());
+    assert!(f.eval("greet GREET").is_ok());
+    assert_eq!("Hello,  World!\nHello,  World!\n", f.output());
+}
+
+#[test]
+fn dot_quote_can_be_empty_and_used_in_control_flow() {
+    let mut f = Forth::new();
+    assert!(f.eval(".\" \" 3 0 do i 2 mod if .\" odd \" else .\" even \" then loop").is_ok());
+    assert_eq!("even odd even ", f.output());
+}
+
+#[test]
+fn unterminated_dot_quote() {
+    let mut f = Forth::new();
+    let error = f.try_eval("1 .\" oops").unwrap_err();
+    assert_eq!(Error::InvalidWord, error.kind);
+    assert_eq!(3, error.column);
+}
+
+#[test]
+fn comments_are_ignored() {
+    let mut f = Forth::new();
+    assert!(f.eval(": square ( n -- n*n ) dup * ;").is_ok());
+    assert!(f.eval("( nothing here )").is_ok());
+    assert!(f.eval("3 square ( unterminated").is_ok());
+    assert_eq!(vec![9], f.stack());
+}
+
+#[test]
+fn words_may_be_separated_by_any_whitespace() {
+    let mut f = Forth::new();
+    assert!(f.eval("  1\t2   + ").is_ok());
+    assert_eq!(vec![3], f.stack());
+}
+
+#[test]
+fn output_can_go_to_a_custom_sink() {
+    let mut f = Forth::<i64, _>::with_output(std::io::Cursor::new(Vec::new()));
+    assert!(f.eval("42 . .\" done\"").is_ok());
+    assert_eq!(b"42 done".to_vec(), f.into_output().into_inner());
+}
+
+// Native words
+
+fn hypot_squared(stack: &mut Stack) -> Result<(), Error> {
+    let b = stack.pop().unwrap();
+    let a = stack.pop().unwrap();
+    stack.push(a * a + b * b);
+    Ok(())
+}
+
+#[test]
+fn native_words_can_be_called() {
+    let mut f = Forth::new();
+    assert!(f.register_native("hypot2", 2, hypot_squared).is_ok());
+    assert!(f.eval("3 4 HYPOT2").is_ok());
+    assert_eq!(vec![25], f.stack());
+}
+
+#[test]
+fn native_words_check_their_arity() {
+    let mut f = Forth::new();
+    assert!(f.register_native("hypot2", 2, hypot_squared).is_ok());
+    assert_eq!(Err(Error::StackUnderflow), f.eval("1 hypot2"));
+}
+
+#[test]
+fn native_words_can_fail() {
+    let mut f = Forth::new();
+    f.set_transactional(true);
+    let result = f.register_native("even!", 1, |stack: &mut Stack| match stack.last() {
+        Some(n) if n % 2 == 0 => Ok(()),
+        _ => Err(Error::InvalidWord),
+    });
+    assert!(result.is_ok());
+    assert!(f.eval("4 even!").is_ok());
+    let error = f.try_eval("3 even!").unwrap_err();
+    assert_eq!(Error::InvalidWord, error.kind);
+    assert_eq!(vec!["even!".to_string()], error.call_stack);
+    assert_eq!(vec![4], f.stack());
+}
+
+#[test]
+fn native_words_can_capture_state() {
+    let calls = std::rc::Rc::new(std::cell::Cell::new(0));
+    let counter = calls.clone();
+    let mut f = Forth::new();
+    assert!(f
+        .register_native("tick", 0, move |_: &mut Stack| {
+            counter.set(counter.get() + 1);
+            Ok(())
+        })
+        .is_ok());
+    assert!(f.eval(": ticks 3 0 do tick loop ;").is_ok());
+    assert!(f.eval("ticks tick").is_ok());
+    assert_eq!(4, calls.get());
+}
+
+#[test]
+fn native_words_follow_snapshot_semantics() {
+    let mut f = Forth::new();
+    assert!(f.register_native("five", 0, |stack: &mut Stack| {
+        stack.push(5);
+        Ok(())
+    }).is_ok());
+    assert!(f.eval(": old-five five ;").is_ok());
+    assert!(f.eval(": five 6 ;").is_ok());
+    assert!(f.eval(": new-five five ;").is_ok());
+    assert!(f.register_native("dup", 1, |stack: &mut Stack| {
+        stack.push(0);
+        Ok(())
+    }).is_ok());
+    assert!(f.eval("old-five new-five dup").is_ok());
+    assert_eq!(vec![5, 6, 0], f.stack());
+}
+
+#[test]
+fn native_words_need_valid_names() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::InvalidWord), f.register_native("12", 0, |_: &mut Stack| Ok(())));
+    assert_eq!(Err(Error::InvalidWord), f.register_native("then", 0, |_: &mut Stack| Ok(())));
+    assert_eq!(Err(Error::InvalidWord), f.register_native("two words", 0, |_: &mut Stack| Ok(())));
+    assert_eq!(Err(Error::InvalidWord), f.register_native("", 0, |_: &mut Stack| Ok(())));
+}
+
+// Limits and tracing
+
+fn limited(limits: Limits) -> Forth {
+    let mut f = Forth::new();
+    f.set_limits(limits);
+    f
+}
+
+#[test]
+fn step_limit_stops_endless_loops() {
+    let mut f = limited(Limits { max_steps: Some(1000), ..Limits::default() });
+    assert!(f.eval(": forever begin 0 until ;").is_ok());
+    assert_eq!(Err(Error::LimitExceeded(Limit::Steps)), f.eval("forever"));
+}
+
+#[test]
+fn step_limit_is_per_line() {
+    let mut f = limited(Limits { max_steps: Some(10), ..Limits::default() });
+    assert!(f.eval("1 2 + 3 4 + 5 6 +").is_ok());
+    assert!(f.eval("1 2 + 3 4 + 5 6 +").is_ok());
+    assert_eq!(Err(Error::LimitExceeded(Limit::Steps)), f.eval("1 1 1 1 1 1 1 1 1 1 1"));
+}
+
+#[test]
+fn stack_depth_limit() {
+    let mut f = limited(Limits { max_stack_depth: Some(3), ..Limits::default() });
+    assert!(f.eval("1 2 3").is_ok());
+    assert_eq!(Err(Error::LimitExceeded(Limit::StackDepth)), f.eval("dup"));
+}
+
+#[test]
+fn dictionary_size_limit_counts_redefinitions() {
+    let mut f = limited(Limits { max_dictionary_size: Some(2), ..Limits::default() });
+    assert!(f.eval(": foo 1 ;").is_ok());
+    assert!(f.eval(": foo 2 ;").is_ok());
+    assert_eq!(Err(Error::LimitExceeded(Limit::DictionarySize)), f.eval(": bar 3 ;"));
+    assert_eq!(Err(Error::LimitExceeded(Limit::DictionarySize)), f.eval("variable x"));
+    assert_eq!(
+        Err(Error::LimitExceeded(Limit::DictionarySize)),
+        f.register_native("baz", 0, |_: &mut Stack| Ok(()))
+    );
+    assert_eq!(Err(Error::UnknownWord), f.eval("bar"));
+}
+
+#[test]
+fn recursion_depth_limit() {
+    let mut f = limited(Limits { max_recursion_depth: Some(2), ..Limits::default() });
+    assert!(f.eval(": one 1 ;").is_ok());
+    assert!(f.eval(": two one ;").is_ok());
+    assert!(f.eval(": three two ;").is_ok());
+    assert!(f.eval("two").is_ok());
+    let error = f.try_eval("three").unwrap_err();
+    assert_eq!(Error::LimitExceeded(Limit::RecursionDepth), error.kind);
+    assert_eq!(vec!["three".to_string(), "two".to_string()], error.call_stack);
+}
+
+#[test]
+fn memory_cells_limit() {
+    let mut f = limited(Limits { max_memory_cells: Some(4), ..Limits::default() });
+    assert!(f.eval("variable x 3 allot").is_ok());
+    assert_eq!(Err(Error::LimitExceeded(Limit::MemoryCells)), f.eval("1 allot"));
+    assert_eq!(Err(Error::LimitExceeded(Limit::MemoryCells)), f.eval("variable y"));
+    assert!(f.eval("-2 allot variable y here").is_ok());
+    assert_eq!(vec![3], f.stack());
+}
+
+#[test]
+fn trace_reports_each_word_with_the_stack() {
+    use std::{cell::RefCell, rc::Rc};
+
+    let steps = Rc::new(RefCell::new(vec![]));
+    let log = steps.clone();
+    let mut f = Forth::new();
+    f.set_trace(move |step| {
+        log.borrow_mut().push(format!(
+            "{}{} {:?} -> {:?}",
+            "  ".repeat(step.depth),
+            step.word,
+            step.before,
+            step.after
+        ))
+    });
+    assert!(f.eval(": Inc 1 + ;").is_ok());
+    assert!(f.eval("1 INC 0 if 5 then").is_ok());
+    assert_eq!(
+        vec![
+            "1 [] -> [1]",
+            "  1 [1] -> [1, 1]",
+            "  + [1, 1] -> [2]",
+            "inc [1] -> [2]",
+            "0 [2] -> [2, 0]",
+            "if [2, 0] -> [2]",
+        ],
+        *steps.borrow()
+    );
+
+    f.clear_trace();
+    assert!(f.eval("inc").is_ok());
+    assert_eq!(6, steps.borrow().len());
+}
@@ -0,0 +1,52 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+fn run_repl(input: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_forth"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("forth binary should start");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn prints_stack_after_each_line() {
+    assert_eq!(
+        "ok <2> 1 2\nok <1> 3\n",
+        run_repl("1 2\n+\n")
+    );
+}
+
+#[test]
+fn errors_report_word_and_column_and_keep_previous_state() {
+    assert_eq!(
+        "ok <1> 1\nerror on line 2: stack underflow at column 5: +\nok <1> 1\n",
+        run_repl("1\n2 + + +\n\n")
+    );
+}
+
+#[test]
+fn definitions_survive_between_lines() {
+    assert_eq!(
+        "ok <0>\nerror on line 2: unknown word at column 3: bar\nok <2> 1 1\n",
+        run_repl(": foo dup ;\n1 bar\n1 foo\n")
+    );
+}
+
+#[test]
+fn printed_text_comes_before_the_stack() {
+    assert_eq!(
+        "Hello, World!\nok <0>\n1 2 \nok <0>\n",
+        run_repl(".\" Hello, World!\" cr\n1 2 swap . .\n")
+    );
+}
@@ -0,0 +1,78 @@
+//! Times `set_value` on large graphs: a chain where every cell depends on the
+//! previous one, a chain of diamonds where every layer splits into two cells
+//! that join again, and a fan of independent cells next to a single cell that
+//! is actually affected by the update. Only affected cells are recomputed, so
+//! the chain and diamonds grow linearly and the fan update stays constant.
+//!
+//! Run with `cargo bench`.
+
+use react::{CellID, InputCellID, Reactor};
+use std::time::{Duration, Instant};
+
+const UPDATES: i64 = 10;
+
+fn time_updates(reactor: &mut Reactor<i64>, input: InputCellID) -> Duration {
+    let start = Instant::now();
+    for value in 1..=UPDATES {
+        assert!(reactor.set_value(input, value));
+    }
+    start.elapsed() / UPDATES as u32
+}
+
+fn chain(cells: usize) -> Duration {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(0);
+    let mut last = CellID::Input(input);
+    for _ in 0..cells {
+        last = CellID::Compute(reactor.create_compute(&[last], |v| v[0] + 1).unwrap());
+    }
+    time_updates(&mut reactor, input)
+}
+
+fn diamonds(cells: usize) -> Duration {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(0);
+    let mut last = CellID::Input(input);
+    for _ in 0..cells / 3 {
+        let left = reactor.create_compute(&[last], |v| v[0] + 1).unwrap();
+        let right = reactor.create_compute(&[last], |v| v[0] - 1).unwrap();
+        let join = reactor
+            .create_compute(&[CellID::Compute(left), CellID::Compute(right)], |v| {
+                (v[0] + v[1]) / 2
+            })
+            .unwrap();
+        last = CellID::Compute(join);
+    }
+    time_updates(&mut reactor, input)
+}
+
+fn fan(cells: usize) -> Duration {
+    let mut reactor = Reactor::new();
+    let unrelated = reactor.create_input(0);
+    let input = reactor.create_input(0);
+    for _ in 0..cells {
+        reactor
+            .create_compute(&[CellID::Input(unrelated)], |v| v[0] + 1)
+            .unwrap();
+    }
+    reactor
+        .create_compute(&[CellID::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    time_updates(&mut reactor, input)
+}
+
+fn main() {
+    println!(
+        "{:>6} | {:>12} | {:>12} | {:>12}",
+        "cells", "chain", "diamonds", "fan"
+    );
+    for &cells in &[100, 1_000, 10_000] {
+        println!(
+            "{:>6} | {:>12?} | {:>12?} | {:>12?}",
+            cells,
+            chain(cells),
+            diamonds(cells),
+            fan(cells)
+        );
+    }
+}
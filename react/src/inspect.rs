@@ -0,0 +1,126 @@
+use crate::{CellID, ComputeCellID, InputCellID, Reactor};
+use std::{
+    convert::Infallible,
+    fmt::{Debug, Write},
+};
+
+/// What a reactor knows about one of its cells, as returned by `Reactor::inspect`.
+#[derive(Debug, PartialEq)]
+pub struct CellInfo<'a, T, E = Infallible> {
+    pub id: CellID,
+    pub value: Result<&'a T, &'a E>,
+    /// Empty for input cells
+    pub dependencies: &'a [CellID],
+    pub dependents: &'a [ComputeCellID],
+    pub callbacks: usize,
+    /// The generation in which a compute cell was last recomputed, `None` for input cells
+    pub recomputed: Option<usize>,
+}
+
+impl<'reactor, T, E> Reactor<'reactor, T, E>
+where
+    T: Clone + PartialEq + Debug,
+    E: Clone + PartialEq + Debug,
+{
+    // Returns the number of `set_value` calls and batches that changed an input cell so far.
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    // Lists the IDs of all cells, input cells first.
+    pub fn cells(&self) -> Vec<CellID> {
+        let inputs = self
+            .input_cells
+            .iter()
+            .map(|(key, _)| CellID::Input(InputCellID(key)));
+        let computes = self
+            .compute_cells
+            .iter()
+            .map(|(key, _)| CellID::Compute(ComputeCellID(key)));
+        inputs.chain(computes).collect()
+    }
+
+    // Describes the cell, or returns None if the cell does not exist. Lazy cells are computed
+    // first, like when reading their value.
+    pub fn inspect(&self, id: CellID) -> Option<CellInfo<'_, T, E>> {
+        match id {
+            CellID::Input(InputCellID(key)) => {
+                let cell = self.input_cells.get(key)?;
+                Some(CellInfo {
+                    id,
+                    value: Ok(&cell.value),
+                    dependencies: &[],
+                    dependents: &cell.dependents,
+                    callbacks: 0,
+                    recomputed: None,
+                })
+            }
+            CellID::Compute(ComputeCellID(key)) => {
+                let cell = self.compute_cells.get(key)?;
+                Some(CellInfo {
+                    id,
+                    value: self.force(key),
+                    dependencies: &cell.dependencies,
+                    dependents: &cell.dependents,
+                    callbacks: cell.callbacks.len(),
+                    recomputed: Some(cell.recomputed.get()),
+                })
+            }
+        }
+    }
+
+    // Renders the cells and their dependencies as a Graphviz graph, with an edge from every
+    // dependency to the cells using it, labelled with the argument position.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph reactor {\n");
+        for id in self.cells() {
+            let cell = self.inspect(id).expect("listed cells exist");
+            let mut label = match cell.value {
+                Ok(value) => format!("{} = {:?}", node_name(id), value),
+                Err(error) => format!("{} failed: {:?}", node_name(id), error),
+            };
+            if cell.callbacks > 0 {
+                write!(label, "\ncallbacks: {}", cell.callbacks).unwrap();
+            }
+            let shape = match id {
+                CellID::Input(_) => "box",
+                CellID::Compute(_) => "ellipse",
+            };
+            writeln!(
+                dot,
+                "    {} [shape={}, label=\"{}\"];",
+                node_name(id),
+                shape,
+                escape(&label)
+            )
+            .unwrap();
+            for (position, &dependency) in cell.dependencies.iter().enumerate() {
+                writeln!(
+                    dot,
+                    "    {} -> {} [label=\"{}\"];",
+                    node_name(dependency),
+                    node_name(id),
+                    position
+                )
+                .unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+// Only one live cell uses each slot, so its index is enough to tell cells apart.
+fn node_name(id: CellID) -> String {
+    match id {
+        CellID::Input(InputCellID(key)) => format!("input{}", key.index()),
+        CellID::Compute(ComputeCellID(key)) => format!("compute{}", key.index()),
+    }
+}
+
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -1,13 +1,27 @@
+mod inspect;
+mod shared;
+mod slots;
+mod typed;
+
+pub use inspect::CellInfo;
+pub use shared::SyncReactor;
+use slots::{Key, Slots};
 use std::{
-    cmp::Ordering,
-    collections::{BinaryHeap, HashMap},
-    fmt::Debug,
+    cell::{Cell, OnceCell, RefCell},
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashMap, HashSet},
+    convert::Infallible,
+    error,
+    fmt::{self, Debug},
+    sync::mpsc::{self, Receiver, Sender},
 };
+pub use typed::{Compute, Dependencies, Handle, Input, TypedReactor};
 
 ///
 /// `InputCellID` is a unique identifier for an input cell.
+/// IDs of removed cells are never reused, so they keep referring to a nonexistent cell.
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub struct InputCellID(usize);
+pub struct InputCellID(Key);
 /// `ComputeCellID` is a unique identifier for a compute cell.
 /// Values of type `InputCellID` and `ComputeCellID` should not be mutually assignable,
 /// demonstrated by the following tests:
@@ -23,7 +37,7 @@ pub struct InputCellID(usize);
 /// let compute: react::InputCellID = r.create_compute(&[react::CellID::Input(input)], |_| 222).unwrap();
 /// ```
 #[derive(Clone, Copy, Eq, Debug, PartialEq)]
-pub struct ComputeCellID(usize);
+pub struct ComputeCellID(Key);
 
 impl PartialOrd for ComputeCellID {
     fn partial_cmp(&self, other: &ComputeCellID) -> Option<Ordering> {
@@ -52,45 +66,191 @@ pub enum RemoveCallbackError {
     NonexistentCallback,
 }
 
-pub struct ComputeCell<'cell, T: Debug + 'cell> {
-    value: Option<T>,
+#[derive(Debug, PartialEq)]
+pub enum RemoveCellError {
+    NonexistentCell,
+    // The compute cells that still depend on the cell, which have to be removed first
+    HasDependents(Vec<ComputeCellID>),
+}
+
+impl fmt::Display for RemoveCellError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RemoveCellError::NonexistentCell => write!(f, "cell does not exist"),
+            RemoveCellError::HasDependents(dependents) => write!(
+                f,
+                "cell is still used by {} compute cell(s): {:?}",
+                dependents.len(),
+                dependents
+            ),
+        }
+    }
+}
+
+impl error::Error for RemoveCellError {}
+
+/// How the value of a compute cell changed, as passed to callbacks added with
+/// `Reactor::add_change_callback`.
+#[derive(Debug, PartialEq)]
+pub enum Change<'a, T, E> {
+    /// The cell has a new value, and had a value before
+    Value(&'a T),
+    /// The compute function or one of the dependencies failed, or failed differently than before
+    Error(&'a E),
+    /// The cell has a value again after an error
+    Recovered(&'a T),
+}
+
+type ComputeFn<T, E> = Box<dyn Fn(&[&T]) -> Result<T, E>>;
+type Callback<'cell, T, E> = Box<dyn FnMut(Change<'_, T, E>) + 'cell>;
+
+struct InputCell<T> {
+    value: T,
+    dependents: Vec<ComputeCellID>,
+    // the reactor generation in which the value last changed
+    changed: usize,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Evaluation {
+    Eager,
+    // Recomputed when read after a dependency changed, unless the cell has callbacks or
+    // subscribers, who have to be told about changes as they happen.
+    Lazy,
+    // Lazy, and only counted as changed when the recomputed value differs from the previous one,
+    // so that its dependents can keep their values.
+    Memoized,
+}
+
+pub struct ComputeCell<'cell, T: Debug + 'cell, E> {
+    // Empty until a lazy cell is first read, and again whenever it is invalidated. It is filled in
+    // through a shared reference, so that reading a cell can compute it.
+    value: OnceCell<Result<T, E>>,
+    // the value of an invalidated lazy cell, to compare the recomputed value with
+    previous: RefCell<Option<Result<T, E>>>,
+    evaluation: Evaluation,
     dependencies: Vec<CellID>,
-    func: Box<Fn(&[T]) -> T>,
-    callbacks: HashMap<usize, Box<FnMut(T) -> () + 'cell>>,
+    dependents: Vec<ComputeCellID>,
+    // Longest path from an input cell. Every dependency has a smaller height, so updating cells in
+    // order of height computes each of them once, after all of its dependencies.
+    height: usize,
+    func: ComputeFn<T, E>,
+    callbacks: HashMap<usize, Callback<'cell, T, E>>,
     callback_counter: usize,
+    subscribers: Vec<Sender<T>>,
+    // the reactor generations in which the cell was last recomputed, in which its value last
+    // changed, and in which it was last brought up to date
+    recomputed: Cell<usize>,
+    changed: Cell<usize>,
+    verified: Cell<usize>,
 }
 
-impl<'cell, T: Debug + 'cell> ComputeCell<'cell, T> {
-    fn new<F: 'static + Fn(&[T]) -> T>(func: F, dependencies: Vec<CellID>) -> Self {
+impl<'cell, T: Debug + 'cell, E> ComputeCell<'cell, T, E> {
+    fn new(
+        func: ComputeFn<T, E>,
+        evaluation: Evaluation,
+        dependencies: Vec<CellID>,
+        height: usize,
+    ) -> Self {
         ComputeCell {
-            value: None,
-            dependencies: dependencies,
-            func: Box::new(func),
+            value: OnceCell::new(),
+            previous: RefCell::new(None),
+            evaluation,
+            dependencies,
+            dependents: vec![],
+            height,
+            func,
             callbacks: HashMap::default(),
             callback_counter: 0,
+            subscribers: vec![],
+            recomputed: Cell::new(0),
+            changed: Cell::new(0),
+            verified: Cell::new(0),
         }
     }
+
+    // Whether the cell is only recomputed once it is read.
+    fn is_lazy(&self) -> bool {
+        self.evaluation != Evaluation::Eager
+            && self.callbacks.is_empty()
+            && self.subscribers.is_empty()
+    }
 }
 
-pub struct Reactor<'reactor, T: Debug> {
-    input_cells: Vec<T>,
-    compute_cells: Vec<ComputeCell<'reactor, T>>,
+// A set of input changes made through `Reactor::batch`, propagated together once it finishes.
+pub struct Transaction<'a, 'reactor, T: Debug, E = Infallible> {
+    reactor: &'a mut Reactor<'reactor, T, E>,
+    // whether any input changed, and the dependents of those that did
+    updated: bool,
+    changed: Vec<ComputeCellID>,
 }
 
-// You are guaranteed that Reactor will only be tested against types that are Copy + PartialEq.
-impl<'reactor, T: Copy + PartialEq + Debug> Reactor<'reactor, T> {
-    pub fn new() -> Self {
+impl<'a, 'reactor, T, E> Transaction<'a, 'reactor, T, E>
+where
+    T: Clone + PartialEq + Debug,
+    E: Clone + PartialEq + Debug,
+{
+    // Sets the value of the specified input cell. Compute cells keep their old values until the
+    // batch is over.
+    //
+    // Returns false if the cell does not exist.
+    pub fn set_value(&mut self, id: InputCellID, new_value: T) -> bool {
+        let InputCellID(key) = id;
+        let cell = match self.reactor.input_cells.get_mut(key) {
+            Some(cell) => cell,
+            None => return false,
+        };
+        if cell.value != new_value {
+            cell.value = new_value;
+            // the generation is advanced once the batch is over
+            cell.changed = self.reactor.generation + 1;
+            self.updated = true;
+            self.changed.extend_from_slice(&cell.dependents);
+        }
+        true
+    }
+}
+
+// `E` is the error type of compute functions created with `create_compute_fallible`.
+pub struct Reactor<'reactor, T: Debug, E = Infallible> {
+    input_cells: Slots<InputCell<T>>,
+    compute_cells: Slots<ComputeCell<'reactor, T, E>>,
+    // number of updates that changed an input cell
+    generation: usize,
+}
+
+impl<'reactor, T: Debug, E> Default for Reactor<'reactor, T, E> {
+    fn default() -> Self {
         Reactor {
-            input_cells: vec![],
-            compute_cells: vec![],
+            input_cells: Slots::new(),
+            compute_cells: Slots::new(),
+            generation: 0,
         }
     }
+}
 
+impl<'reactor, T: Clone + PartialEq + Debug> Reactor<'reactor, T> {
+    // Creates a reactor whose compute functions cannot fail. Use `Reactor::default()` for one
+    // with fallible compute functions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+// Values and errors are compared to decide whether a change has to be propagated, and are only
+// cloned when read through `value` or passed on to dependents.
+impl<'reactor, T, E> Reactor<'reactor, T, E>
+where
+    T: Clone + PartialEq + Debug,
+    E: Clone + PartialEq + Debug,
+{
     // Creates an input cell with the specified initial value, returning its ID.
     pub fn create_input(&mut self, initial: T) -> InputCellID {
-        let id = self.input_cells.len();
-        self.input_cells.push(initial);
-        InputCellID(id)
+        InputCellID(self.input_cells.insert(InputCell {
+            value: initial,
+            dependents: vec![],
+            changed: self.generation,
+        }))
     }
 
     // Creates a compute cell with the specified dependencies and compute function.
@@ -99,26 +259,133 @@ impl<'reactor, T: Copy + PartialEq + Debug> Reactor<'reactor, T> {
     // You do not need to reject compute functions that expect more arguments than there are
     // dependencies (how would you check for this, anyway?).
     //
-    // If any dependency doesn't exist, returns an Err with that nonexistent dependency.
+    // If any dependency doesn't exist, returns an Err with that nonexistent dependency, and no
+    // cell is created.
     // (If multiple dependencies do not exist, exactly which one is returned is not defined and
     // will not be tested)
     //
-    // Notice that there is no way to *remove* a cell.
-    // This means that you may assume, without checking, that if the dependencies exist at creation
-    // time they will continue to exist as long as the Reactor exists.
-    pub fn create_compute<F: 'static + Fn(&[T]) -> T>(
+    // Cells can only be removed once no other cell depends on them, so the dependencies of a cell
+    // exist as long as the cell does.
+    pub fn create_compute<F: 'static + Fn(&[&T]) -> T>(
+        &mut self,
+        dependencies: &[CellID],
+        compute_func: F,
+    ) -> Result<ComputeCellID, CellID> {
+        let func = Box::new(move |args: &[&T]| Ok(compute_func(args)));
+        self.insert_compute(dependencies, func, Evaluation::Eager)
+    }
+
+    // Creates a compute cell like `create_compute` that is only computed when its value is read,
+    // rather than whenever a dependency changes.
+    //
+    // Lazy cells with callbacks or subscriptions are computed like other cells while those exist,
+    // to notice changes when they happen.
+    pub fn create_lazy_compute<F: 'static + Fn(&[&T]) -> T>(
+        &mut self,
+        dependencies: &[CellID],
+        compute_func: F,
+    ) -> Result<ComputeCellID, CellID> {
+        let func = Box::new(move |args: &[&T]| Ok(compute_func(args)));
+        self.insert_compute(dependencies, func, Evaluation::Lazy)
+    }
+
+    // Creates a lazy compute cell like `create_lazy_compute` that compares its recomputed values
+    // to the previous ones. Its dependents, lazy or not, are then only recomputed if its value
+    // actually changed.
+    pub fn create_memoized_compute<F: 'static + Fn(&[&T]) -> T>(
+        &mut self,
+        dependencies: &[CellID],
+        compute_func: F,
+    ) -> Result<ComputeCellID, CellID> {
+        let func = Box::new(move |args: &[&T]| Ok(compute_func(args)));
+        self.insert_compute(dependencies, func, Evaluation::Memoized)
+    }
+
+    // Creates a compute cell whose compute function may fail, just like `create_compute`.
+    //
+    // A cell whose compute function returned an error is in an error state, which `result`
+    // exposes. Compute cells depending on it are in the same error state, without calling their
+    // compute functions, until it has a value again.
+    pub fn create_compute_fallible<F: 'static + Fn(&[&T]) -> Result<T, E>>(
         &mut self,
         dependencies: &[CellID],
         compute_func: F,
     ) -> Result<ComputeCellID, CellID> {
-        let compute_id = self.compute_cells.len();
-        let cell = ComputeCell::new(compute_func, dependencies.to_vec());
-        self.compute_cells.push(cell);
-        self.calculate(ComputeCellID(compute_id))?;
-        Ok(ComputeCellID(compute_id))
+        self.insert_compute(dependencies, Box::new(compute_func), Evaluation::Eager)
+    }
+
+    fn insert_compute(
+        &mut self,
+        dependencies: &[CellID],
+        compute_func: ComputeFn<T, E>,
+        evaluation: Evaluation,
+    ) -> Result<ComputeCellID, CellID> {
+        let mut height = 0;
+        for &dependency in dependencies {
+            match dependency {
+                CellID::Input(InputCellID(key)) if self.input_cells.get(key).is_some() => {}
+                CellID::Compute(ComputeCellID(key)) => match self.compute_cells.get(key) {
+                    Some(cell) => height = height.max(cell.height),
+                    None => return Err(dependency),
+                },
+                _ => return Err(dependency),
+            }
+        }
+
+        let cell = ComputeCell::new(compute_func, evaluation, dependencies.to_vec(), height + 1);
+        let id = ComputeCellID(self.compute_cells.insert(cell));
+        for &dependency in dependencies {
+            let dependents = self.dependents_mut(dependency);
+            if !dependents.contains(&id) {
+                dependents.push(id);
+            }
+        }
+        if evaluation == Evaluation::Eager {
+            self.calculate(id);
+        }
+        Ok(id)
+    }
+
+    // Removes a cell, so that its ID no longer refers to any cell.
+    //
+    // Returns an Err if the cell does not exist, or if compute cells still depend on it. Those
+    // have to be removed first.
+    pub fn remove_cell(&mut self, id: CellID) -> Result<(), RemoveCellError> {
+        let dependents = match id {
+            CellID::Input(InputCellID(key)) => self.input_cells.get(key).map(|c| &c.dependents),
+            CellID::Compute(ComputeCellID(key)) => {
+                self.compute_cells.get(key).map(|c| &c.dependents)
+            }
+        }
+        .ok_or(RemoveCellError::NonexistentCell)?;
+        if !dependents.is_empty() {
+            return Err(RemoveCellError::HasDependents(dependents.clone()));
+        }
+
+        match id {
+            CellID::Input(InputCellID(key)) => {
+                self.input_cells.remove(key);
+            }
+            CellID::Compute(compute_id @ ComputeCellID(key)) => {
+                let cell = self.compute_cells.remove(key).expect("cell exists");
+                for dependency in cell.dependencies {
+                    self.dependents_mut(dependency)
+                        .retain(|&dependent| dependent != compute_id);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn dependents_mut(&mut self, id: CellID) -> &mut Vec<ComputeCellID> {
+        match id {
+            CellID::Input(InputCellID(key)) => &mut self.input_cells[key].dependents,
+            CellID::Compute(ComputeCellID(key)) => &mut self.compute_cells[key].dependents,
+        }
     }
 
-    // Retrieves the current value of the cell, or None if the cell does not exist.
+    // Retrieves the current value of the cell, or None if the cell does not exist or is in an
+    // error state.
     //
     // You may wonder whether it is possible to implement `get(&self, id: CellID) -> Option<&Cell>`
     // and have a `value(&self)` method on `Cell`.
@@ -126,10 +393,116 @@ impl<'reactor, T: Copy + PartialEq + Debug> Reactor<'reactor, T> {
     // It turns out this introduces a significant amount of extra complexity to this exercise.
     // We chose not to cover this here, since this exercise is probably enough work as-is.
     pub fn value(&self, id: CellID) -> Option<T> {
+        self.value_ref(id).cloned()
+    }
+
+    // Borrows the current value of the cell, or returns None if the cell does not exist or is in
+    // an error state.
+    pub fn value_ref(&self, id: CellID) -> Option<&T> {
+        self.result(id)?.ok()
+    }
+
+    // Borrows the current value or error of the cell, or returns None if the cell does not exist.
+    pub fn result(&self, id: CellID) -> Option<Result<&T, &E>> {
         match id {
-            CellID::Input(InputCellID(i)) => self.input_cells.get(i).map(|t| t.clone()),
-            CellID::Compute(ComputeCellID(i)) => self.compute_cells.get(i)?.value,
+            CellID::Input(InputCellID(key)) => {
+                self.input_cells.get(key).map(|cell| Ok(&cell.value))
+            }
+            CellID::Compute(ComputeCellID(key)) => {
+                self.compute_cells.get(key)?;
+                Some(self.force(key))
+            }
+        }
+    }
+
+    // Borrows the value or error of a compute cell, first computing it if it is lazy and out of
+    // date.
+    fn force(&self, key: Key) -> Result<&T, &E> {
+        if self.compute_cells[key].value.get().is_none() {
+            // Out of date ancestors are brought up to date lowest first, so that computing each
+            // one finds its dependencies ready instead of recursing through a long lazy chain.
+            for ancestor in self.stale_ancestors(key) {
+                self.evaluate(ancestor).ok();
+            }
         }
+        self.evaluate(key)
+    }
+
+    // Returns the out of date compute cells that the cell depends on, in order of height.
+    fn stale_ancestors(&self, key: Key) -> Vec<Key> {
+        let mut stale = vec![];
+        let mut seen = HashSet::new();
+        let mut pending = vec![key];
+        while let Some(key) = pending.pop() {
+            for &dep in &self.compute_cells[key].dependencies {
+                if let CellID::Compute(ComputeCellID(dep)) = dep {
+                    if self.compute_cells[dep].value.get().is_none() && seen.insert(dep) {
+                        stale.push(dep);
+                        pending.push(dep);
+                    }
+                }
+            }
+        }
+        stale.sort_by_key(|&key| self.compute_cells[key].height);
+        stale
+    }
+
+    // Borrows the value or error of a compute cell, computing it if it is out of date.
+    fn evaluate(&self, key: Key) -> Result<&T, &E> {
+        let cell = &self.compute_cells[key];
+        cell.value
+            .get_or_init(|| {
+                let previous = cell.previous.take();
+                let verified = cell.verified.replace(self.generation);
+                // computing the arguments brings lazy dependencies up to date, so their `changed`
+                // generations can be trusted below
+                let args = self.arguments(cell);
+                let previous = match previous {
+                    Some(previous) if !self.dependencies_changed(cell, verified) => {
+                        return previous;
+                    }
+                    previous => previous,
+                };
+                let result = match args {
+                    Ok(args) => (*cell.func)(args.as_slice()),
+                    Err(error) => Err(error.clone()),
+                };
+                cell.recomputed.set(self.generation);
+                if cell.evaluation == Evaluation::Lazy || previous.as_ref() != Some(&result) {
+                    cell.changed.set(self.generation);
+                }
+                result
+            })
+            .as_ref()
+    }
+
+    // Returns whether the value of any dependency of the cell changed after the given generation.
+    // Lazy dependencies queue their dependents before knowing whether their value changes, and
+    // this tells whether it did.
+    fn dependencies_changed(&self, cell: &ComputeCell<'reactor, T, E>, since: usize) -> bool {
+        cell.dependencies
+            .iter()
+            .any(|&dep| self.changed(dep) > since)
+    }
+
+    // Returns the generation in which the value of the cell last changed.
+    fn changed(&self, id: CellID) -> usize {
+        match id {
+            CellID::Input(InputCellID(key)) => self.input_cells[key].changed,
+            CellID::Compute(ComputeCellID(key)) => self.compute_cells[key].changed.get(),
+        }
+    }
+
+    // Collects the values of the dependencies of a compute cell, or the first error among them.
+    fn arguments(&self, cell: &ComputeCell<'reactor, T, E>) -> Result<Vec<&T>, &E> {
+        // dependencies cannot be removed before the cell, so they exist
+        cell.dependencies
+            .iter()
+            .map(|&dep| {
+                self.result(dep)
+                    .expect("dependencies outlive their dependents")
+            })
+            .collect()
     }
 
     // Sets the value of the specified input cell.
@@ -141,82 +514,111 @@ impl<'reactor, T: Copy + PartialEq + Debug> Reactor<'reactor, T> {
     //
     // As before, that turned out to add too much extra complexity.
     pub fn set_value(&mut self, id: InputCellID, new_value: T) -> bool {
-        let InputCellID(i) = id;
-        let cell = self.input_cells.get_mut(i).map(|cell| *cell = new_value);
+        self.batch(|tx| tx.set_value(id, new_value))
+    }
 
-        if cell.is_none() {
-            return false;
+    // Runs `f` with a transaction that sets input cells without updating their dependents, then
+    // updates all affected compute cells at once, returning the result of `f`.
+    //
+    // Each compute cell is recomputed at most once, so its callbacks are called at most once,
+    // with the value computed from the final values of all inputs.
+    pub fn batch<R, F: FnOnce(&mut Transaction<'_, 'reactor, T, E>) -> R>(&mut self, f: F) -> R {
+        let mut tx = Transaction {
+            reactor: self,
+            updated: false,
+            changed: vec![],
+        };
+        let result = f(&mut tx);
+        let Transaction {
+            reactor,
+            updated,
+            changed,
+        } = tx;
+        if updated {
+            reactor.generation += 1;
         }
+        reactor.propagate(&changed);
+        result
+    }
 
-        let mut dependencies = self
-            .compute_cells
+    // Recomputes the given cells and, for those whose value changed, their dependents.
+    //
+    // Cells are taken lowest first, so a cell is only recomputed once all of its dependencies
+    // are up to date, and only cells reachable from `changed` through changed values are visited.
+    // Unobserved lazy cells are only marked out of date, and their dependents are visited as if
+    // their value changed.
+    fn propagate(&mut self, changed: &[ComputeCellID]) {
+        let cells = &self.compute_cells;
+        let mut pending = changed
             .iter()
-            .enumerate()
-            .filter_map(
-                |(i, comp)| match comp.dependencies.contains(&CellID::Input(id)) {
-                    true => Some(ComputeCellID(i)),
-                    false => None,
-                },
-            )
+            .map(|&ComputeCellID(key)| Reverse((cells[key].height, key)))
             .collect::<BinaryHeap<_>>();
-
-        while let Some(ComputeCellID(compute_id)) = dependencies.pop() {
-            let cell_id = ComputeCellID(compute_id);
-            // can ignore this error since all cell ids are validated upon cell creation,
-            // therefore calculation cannot fail at this time
-            let _ = self.calculate(cell_id);
-            self.compute_cells.iter().enumerate().for_each(|(i, comp)| {
-                if comp.dependencies.contains(&CellID::Compute(cell_id)) {
-                    dependencies.push(ComputeCellID(i));
-                }
-            });
-        }
-
-        true
-    }
-
-    fn calculate(&mut self, id: ComputeCellID) -> Result<T, CellID> {
-        let ComputeCellID(compute_id) = id;
-        let cell = self
-            .compute_cells
-            .get(compute_id)
-            .ok_or_else(|| CellID::Compute(ComputeCellID(compute_id)))?;
-
-        let args = cell.dependencies.iter().try_fold(vec![], |mut args, dep| {
-            let val = match dep {
-                CellID::Input(InputCellID(id)) => {
-                    let cell = self
-                        .input_cells
-                        .get(*id)
-                        .ok_or_else(|| CellID::Input(InputCellID(*id)))?;
-                    *cell
-                }
-                CellID::Compute(ComputeCellID(id)) => {
-                    let cell = self
-                        .compute_cells
-                        .get(*id)
-                        .ok_or_else(|| CellID::Compute(ComputeCellID(*id)))?;
-                    cell.value
-                        .ok_or_else(|| CellID::Compute(ComputeCellID(*id)))?
+        let mut last = None;
+        while let Some(Reverse((_, key))) = pending.pop() {
+            // a cell depending on several changed cells is queued once for each of them, and the
+            // copies are popped one after another
+            if last.replace(key) == Some(key) {
+                continue;
+            }
+            let cell = &mut self.compute_cells[key];
+            let changed = if cell.is_lazy() {
+                if let Some(value) = cell.value.take() {
+                    *cell.previous.get_mut() = Some(value);
                 }
+                true
+            } else {
+                self.calculate(ComputeCellID(key))
             };
-            args.push(val);
-            Ok(args)
-        })?;
-        let result = (*cell.func)(args.as_slice());
-        match self.compute_cells.get_mut(compute_id) {
-            Some(cell) => {
-                if cell.value.replace(result) != Some(result) {
-                    for cb in cell.callbacks.values_mut() {
-                        cb(result);
-                    }
+            if changed {
+                let cells = &self.compute_cells;
+                for &ComputeCellID(dependent) in &cells[key].dependents {
+                    pending.push(Reverse((cells[dependent].height, dependent)));
                 }
-                Ok(result)
             }
-            None => Err(CellID::Compute(ComputeCellID(compute_id))),
         }
     }
 
+    // Recomputes a compute cell from the current values of its dependencies, calling its
+    // callbacks if the value or error changed. Returns whether it did.
+    fn calculate(&mut self, id: ComputeCellID) -> bool {
+        let ComputeCellID(key) = id;
+        let cell = &self.compute_cells[key];
+        // dependencies have a smaller height, so they are already up to date
+        let args = self.arguments(cell);
+        if cell.value.get().is_some() && !self.dependencies_changed(cell, cell.verified.get()) {
+            cell.verified.set(self.generation);
+            return false;
+        }
+        let result = match args {
+            Ok(args) => (*cell.func)(args.as_slice()),
+            Err(error) => Err(error.clone()),
+        };
+
+        let cell = &mut self.compute_cells[key];
+        cell.recomputed.set(self.generation);
+        cell.verified.set(self.generation);
+        if cell.value.get() == Some(&result) {
+            return false;
+        }
+        cell.changed.set(self.generation);
+        let recovered = matches!(cell.value.get(), Some(Err(_)));
+        cell.value = OnceCell::from(result);
+        let state = cell.value.get().expect("just set");
+        for cb in cell.callbacks.values_mut() {
+            cb(match state {
+                Ok(value) if recovered => Change::Recovered(value),
+                Ok(value) => Change::Value(value),
+                Err(error) => Change::Error(error),
+            });
+        }
+        if let Ok(value) = state {
+            // a failed send means the receiver was dropped, so nobody is listening anymore
+            cell.subscribers
+                .retain(|subscriber| subscriber.send(value.clone()).is_ok());
+        }
+        true
+    }
+
     // Adds a callback to the specified compute cell.
     //
     // Returns the ID of the just-added callback, or None if the cell doesn't exist.
@@ -229,13 +631,32 @@ impl<'reactor, T: Copy + PartialEq + Debug> Reactor<'reactor, T> {
     // * Exactly once if the compute cell's value changed as a result of the set_value call.
     //   The value passed to the callback should be the final value of the compute cell after the
     //   set_value call.
-    pub fn add_callback<F: FnMut(T) -> () + 'reactor>(
+    //
+    // Callbacks are not called when the cell enters an error state, but are called once it has a
+    // value again, even if that is the value it had before the error.
+    pub fn add_callback<F: FnMut(&T) + 'reactor>(
+        &mut self,
+        id: ComputeCellID,
+        mut callback: F,
+    ) -> Option<CallbackID> {
+        self.add_change_callback(id, move |change| match change {
+            Change::Value(value) | Change::Recovered(value) => callback(value),
+            Change::Error(_) => {}
+        })
+    }
+
+    // Adds a callback to the specified compute cell that is also called when the cell enters an
+    // error state, and that can tell values after an error apart from ordinary changes.
+    pub fn add_change_callback<F: FnMut(Change<'_, T, E>) + 'reactor>(
         &mut self,
         id: ComputeCellID,
         callback: F,
     ) -> Option<CallbackID> {
         let ComputeCellID(compute_id) = id;
-        let compute_cell = self.compute_cells.get_mut(compute_id)?;
+        self.compute_cells.get(compute_id)?;
+        // an observed lazy cell is kept up to date, starting from its current value
+        let _ = self.force(compute_id);
+        let compute_cell = &mut self.compute_cells[compute_id];
         let callback_id = compute_cell.callback_counter;
         compute_cell
             .callbacks
@@ -244,6 +665,23 @@ impl<'reactor, T: Copy + PartialEq + Debug> Reactor<'reactor, T> {
         Some(CallbackID(callback_id))
     }
 
+    // Subscribes to the specified compute cell, returning a channel that receives the new value
+    // whenever a callback added with `add_callback` would be called, or None if the cell doesn't
+    // exist.
+    //
+    // Unlike callbacks, the receiver does not borrow the reactor, so it can be moved to another
+    // thread (if `T` is `Send`). Dropping it ends the subscription, and removing the cell or
+    // dropping the reactor ends the stream of values.
+    pub fn subscribe(&mut self, id: ComputeCellID) -> Option<Receiver<T>> {
+        let ComputeCellID(key) = id;
+        self.compute_cells.get(key)?;
+        let _ = self.force(key);
+        let compute_cell = &mut self.compute_cells[key];
+        let (sender, receiver) = mpsc::channel();
+        compute_cell.subscribers.push(sender);
+        Some(receiver)
+    }
+
     // Removes the specified callback, using an ID returned from add_callback.
     //
     // Returns an Err if either the cell or callback does not exist.
@@ -259,11 +697,11 @@ impl<'reactor, T: Copy + PartialEq + Debug> Reactor<'reactor, T> {
         let compute_cell = self
             .compute_cells
             .get_mut(compute_id)
-            .ok_or_else(|| RemoveCallbackError::NonexistentCell)?;
+            .ok_or(RemoveCallbackError::NonexistentCell)?;
         compute_cell
             .callbacks
             .remove(&callback_id)
-            .ok_or_else(|| RemoveCallbackError::NonexistentCallback)?;
-        Ok(())
+            .map(|_| ())
+            .ok_or(RemoveCallbackError::NonexistentCallback)
     }
 }
@@ -0,0 +1,109 @@
+use crate::{
+    CallbackID, CellID, ComputeCellID, InputCellID, Reactor, RemoveCallbackError, RemoveCellError,
+    Transaction,
+};
+use std::{
+    fmt::Debug,
+    sync::{mpsc::Receiver, Mutex, MutexGuard},
+};
+
+struct SendReactor<T: Debug + 'static>(Reactor<'static, T>);
+
+// SAFETY: the compute functions and callbacks boxed inside a `Reactor` are not required to be
+// `Send`, which is all that keeps it from being `Send` itself. A `SendReactor` is only reachable
+// through `SyncReactor`, which only adds `Send` closures, never hands out mutable access to the
+// reactor, and holds `T: Send` values and senders.
+unsafe impl<T: Debug + Send + 'static> Send for SendReactor<T> {}
+
+/// A `Reactor` that can be shared between threads, for example in an `Arc`.
+///
+/// Every operation locks the whole reactor, so an update is propagated completely before any
+/// other thread can read or change a cell. Compute functions and callbacks run while the lock is
+/// held, and must not use the same `SyncReactor` themselves.
+///
+/// Closures have to be `Send`, since whichever thread holds the lock may run them:
+///
+/// ```compile_fail
+/// let reactor = react::SyncReactor::new();
+/// let input = reactor.create_input(1);
+/// let shared = std::rc::Rc::new(2);
+/// let _ = reactor.create_compute(&[react::CellID::Input(input)], move |v| v[0] * *shared);
+/// ```
+pub struct SyncReactor<T: Debug + 'static> {
+    reactor: Mutex<SendReactor<T>>,
+}
+
+impl<T: Clone + PartialEq + Debug + Send + 'static> Default for SyncReactor<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone + PartialEq + Debug + Send + 'static> SyncReactor<T> {
+    pub fn new() -> Self {
+        SyncReactor {
+            reactor: Mutex::new(SendReactor(Reactor::new())),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SendReactor<T>> {
+        // a panicking compute function or callback may leave cells half updated
+        self.reactor
+            .lock()
+            .expect("a compute function or callback panicked")
+    }
+
+    pub fn create_input(&self, initial: T) -> InputCellID {
+        self.lock().0.create_input(initial)
+    }
+
+    pub fn create_compute<F: 'static + Send + Fn(&[&T]) -> T>(
+        &self,
+        dependencies: &[CellID],
+        compute_func: F,
+    ) -> Result<ComputeCellID, CellID> {
+        self.lock().0.create_compute(dependencies, compute_func)
+    }
+
+    pub fn remove_cell(&self, id: CellID) -> Result<(), RemoveCellError> {
+        self.lock().0.remove_cell(id)
+    }
+
+    pub fn value(&self, id: CellID) -> Option<T> {
+        self.lock().0.value(id)
+    }
+
+    // Runs `f` on the reactor while holding the lock, to read several cells from the same
+    // stable state.
+    pub fn read<R, F: FnOnce(&Reactor<'static, T>) -> R>(&self, f: F) -> R {
+        f(&self.lock().0)
+    }
+
+    pub fn set_value(&self, id: InputCellID, new_value: T) -> bool {
+        self.lock().0.set_value(id, new_value)
+    }
+
+    pub fn batch<R, F: FnOnce(&mut Transaction<'_, 'static, T>) -> R>(&self, f: F) -> R {
+        self.lock().0.batch(f)
+    }
+
+    pub fn add_callback<F: FnMut(&T) + Send + 'static>(
+        &self,
+        id: ComputeCellID,
+        callback: F,
+    ) -> Option<CallbackID> {
+        self.lock().0.add_callback(id, callback)
+    }
+
+    pub fn remove_callback(
+        &self,
+        id: ComputeCellID,
+        callback: CallbackID,
+    ) -> Result<(), RemoveCallbackError> {
+        self.lock().0.remove_callback(id, callback)
+    }
+
+    pub fn subscribe(&self, id: ComputeCellID) -> Option<Receiver<T>> {
+        self.lock().0.subscribe(id)
+    }
+}
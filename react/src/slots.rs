@@ -0,0 +1,110 @@
+use std::ops::{Index, IndexMut};
+
+/// Identifies a value in `Slots`. The generation tells apart values that used the same slot, so a
+/// key of a removed value never refers to a value inserted later.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Key {
+    index: usize,
+    generation: usize,
+}
+
+impl Key {
+    pub fn index(self) -> usize {
+        self.index
+    }
+}
+
+struct Slot<V> {
+    generation: usize,
+    value: Option<V>,
+}
+
+/// A vector of values whose slots are reused after removal.
+pub struct Slots<V> {
+    slots: Vec<Slot<V>>,
+    free: Vec<usize>,
+}
+
+impl<V> Slots<V> {
+    pub fn new() -> Self {
+        Slots {
+            slots: vec![],
+            free: vec![],
+        }
+    }
+
+    pub fn insert(&mut self, value: V) -> Key {
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                slot.value = Some(value);
+                Key {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    value: Some(value),
+                });
+                Key {
+                    index: self.slots.len() - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    pub fn get(&self, key: Key) -> Option<&V> {
+        self.slots
+            .get(key.index)
+            .filter(|slot| slot.generation == key.generation)?
+            .value
+            .as_ref()
+    }
+
+    pub fn get_mut(&mut self, key: Key) -> Option<&mut V> {
+        self.slots
+            .get_mut(key.index)
+            .filter(|slot| slot.generation == key.generation)?
+            .value
+            .as_mut()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Key, &V)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            let key = Key {
+                index,
+                generation: slot.generation,
+            };
+            slot.value.as_ref().map(|value| (key, value))
+        })
+    }
+
+    pub fn remove(&mut self, key: Key) -> Option<V> {
+        let slot = self
+            .slots
+            .get_mut(key.index)
+            .filter(|slot| slot.generation == key.generation)?;
+        let value = slot.value.take()?;
+        slot.generation += 1;
+        self.free.push(key.index);
+        Some(value)
+    }
+}
+
+/// Indexing panics if the key is stale, so it is only used for keys known to be alive.
+impl<V> Index<Key> for Slots<V> {
+    type Output = V;
+
+    fn index(&self, key: Key) -> &V {
+        self.get(key).expect("stale key")
+    }
+}
+
+impl<V> IndexMut<Key> for Slots<V> {
+    fn index_mut(&mut self, key: Key) -> &mut V {
+        self.get_mut(key).expect("stale key")
+    }
+}
@@ -0,0 +1,260 @@
+use crate::{
+    CallbackID, CellID, ComputeCellID, InputCellID, Reactor, RemoveCallbackError, RemoveCellError,
+};
+use std::{
+    any::{Any, TypeId},
+    fmt::{self, Debug},
+    marker::PhantomData,
+    rc::Rc,
+};
+
+/// The values of a `TypedReactor`, which are stored in a `Reactor` whose cells all have one type.
+trait Value: Any + Debug {
+    fn as_any(&self) -> &dyn Any;
+    fn equals(&self, other: &dyn Value) -> bool;
+}
+
+impl<T: Any + PartialEq + Debug> Value for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn equals(&self, other: &dyn Value) -> bool {
+        other.as_any().downcast_ref::<T>() == Some(self)
+    }
+}
+
+#[derive(Clone)]
+pub struct Dynamic(Rc<dyn Value>);
+
+impl Dynamic {
+    fn new<T: Any + PartialEq + Debug>(value: T) -> Self {
+        Dynamic(Rc::new(value))
+    }
+
+    fn downcast<T: Any>(&self) -> Option<&T> {
+        self.0.as_any().downcast_ref()
+    }
+
+    fn type_id(&self) -> TypeId {
+        self.0.as_any().type_id()
+    }
+}
+
+impl PartialEq for Dynamic {
+    fn eq(&self, other: &Dynamic) -> bool {
+        self.0.equals(&*other.0)
+    }
+}
+
+impl Debug for Dynamic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// A typed cell of a `TypedReactor`, usable as a dependency of compute cells.
+pub trait Handle: Copy {
+    type Value: Any + PartialEq + Debug;
+
+    fn id(&self) -> CellID;
+}
+
+/// Identifies an input cell holding a `T`.
+pub struct Input<T> {
+    id: InputCellID,
+    marker: PhantomData<fn() -> T>,
+}
+
+/// Identifies a compute cell holding a `T`.
+pub struct Compute<T> {
+    id: ComputeCellID,
+    marker: PhantomData<fn() -> T>,
+}
+
+// Handles are copyable whatever their value type is, so the impls are written out instead of
+// derived.
+macro_rules! handle {
+    ($handle:ident, $variant:ident, $id:ty) => {
+        impl<T> $handle<T> {
+            fn new(id: $id) -> Self {
+                $handle {
+                    id,
+                    marker: PhantomData,
+                }
+            }
+        }
+
+        impl<T> Clone for $handle<T> {
+            fn clone(&self) -> Self {
+                *self
+            }
+        }
+
+        impl<T> Copy for $handle<T> {}
+
+        impl<T> PartialEq for $handle<T> {
+            fn eq(&self, other: &Self) -> bool {
+                self.id == other.id
+            }
+        }
+
+        impl<T> Debug for $handle<T> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                self.id.fmt(f)
+            }
+        }
+
+        impl<T: Any + PartialEq + Debug> Handle for $handle<T> {
+            type Value = T;
+
+            fn id(&self) -> CellID {
+                CellID::$variant(self.id)
+            }
+        }
+    };
+}
+
+handle!(Input, Input, InputCellID);
+handle!(Compute, Compute, ComputeCellID);
+
+/// A tuple of handles that a compute cell depends on. The compute function receives a tuple of
+/// references to their values, in the same order.
+pub trait Dependencies {
+    type Values<'a>;
+
+    fn ids(&self) -> Vec<CellID>;
+    fn type_ids() -> Vec<TypeId>;
+    fn values<'a>(values: &[&'a Dynamic]) -> Self::Values<'a>;
+}
+
+macro_rules! dependencies {
+    ($($handle:ident $index:tt),+) => {
+        impl<$($handle: Handle),+> Dependencies for ($($handle,)+) {
+            type Values<'a> = ($(&'a $handle::Value,)+);
+
+            fn ids(&self) -> Vec<CellID> {
+                vec![$(self.$index.id()),+]
+            }
+
+            fn type_ids() -> Vec<TypeId> {
+                vec![$(TypeId::of::<$handle::Value>()),+]
+            }
+
+            fn values<'a>(values: &[&'a Dynamic]) -> Self::Values<'a> {
+                ($(values[$index].downcast().expect("dependency types are checked"),)+)
+            }
+        }
+    };
+}
+
+dependencies!(A 0);
+dependencies!(A 0, B 1);
+dependencies!(A 0, B 1, C 2);
+dependencies!(A 0, B 1, C 2, D 3);
+dependencies!(A 0, B 1, C 2, D 3, E 4);
+dependencies!(A 0, B 1, C 2, D 3, E 4, F 5);
+
+/// A reactor whose cells can each hold a different type, accessed through typed handles.
+///
+/// ```
+/// use react::TypedReactor;
+///
+/// let mut reactor = TypedReactor::new();
+/// let celsius = reactor.create_input(21.5);
+/// let label = reactor
+///     .create_compute((celsius,), |(c,)| format!("{:.1}°F", c * 1.8 + 32.0))
+///     .unwrap();
+/// assert_eq!(reactor.value(label).map(String::as_str), Some("70.7°F"));
+/// ```
+///
+/// Dependencies of the wrong type are rejected at compile time:
+///
+/// ```compile_fail
+/// let mut reactor = react::TypedReactor::new();
+/// let number = reactor.create_input(1);
+/// let _ = reactor.create_compute((number,), |(s,): (&String,)| s.len());
+/// ```
+pub struct TypedReactor<'reactor> {
+    cells: Reactor<'reactor, Dynamic>,
+}
+
+impl<'reactor> Default for TypedReactor<'reactor> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'reactor> TypedReactor<'reactor> {
+    pub fn new() -> Self {
+        TypedReactor {
+            cells: Reactor::new(),
+        }
+    }
+
+    pub fn create_input<T: Any + PartialEq + Debug>(&mut self, initial: T) -> Input<T> {
+        Input::new(self.cells.create_input(Dynamic::new(initial)))
+    }
+
+    // Creates a compute cell from a tuple of dependency handles, such as `(a, b)`.
+    //
+    // If any dependency doesn't exist in this reactor, returns an Err with that dependency.
+    pub fn create_compute<D, U, F>(
+        &mut self,
+        dependencies: D,
+        compute_func: F,
+    ) -> Result<Compute<U>, CellID>
+    where
+        D: Dependencies,
+        U: Any + PartialEq + Debug,
+        F: 'static + Fn(D::Values<'_>) -> U,
+    {
+        let ids = dependencies.ids();
+        // a handle from another reactor could name a cell of a different type here
+        for (&id, type_id) in ids.iter().zip(D::type_ids()) {
+            match self.cells.value_ref(id) {
+                Some(value) if value.type_id() == type_id => {}
+                _ => return Err(id),
+            }
+        }
+        let id = self.cells.create_compute(&ids, move |values| {
+            Dynamic::new(compute_func(D::values(values)))
+        })?;
+        Ok(Compute::new(id))
+    }
+
+    // Borrows the current value of the cell, or returns None if the cell does not exist.
+    pub fn value<H: Handle>(&self, cell: H) -> Option<&H::Value> {
+        self.cells.value_ref(cell.id())?.downcast()
+    }
+
+    // Sets the value of the specified input cell, returning false if the cell does not exist.
+    pub fn set_value<T: Any + PartialEq + Debug>(&mut self, cell: Input<T>, new_value: T) -> bool {
+        self.value(cell).is_some() && self.cells.set_value(cell.id, Dynamic::new(new_value))
+    }
+
+    pub fn remove_cell<H: Handle>(&mut self, cell: H) -> Result<(), RemoveCellError> {
+        self.cells.remove_cell(cell.id())
+    }
+
+    // Adds a callback to the specified compute cell, with the same semantics as
+    // `Reactor::add_callback`.
+    pub fn add_callback<T: Any + PartialEq + Debug, F: FnMut(&T) + 'reactor>(
+        &mut self,
+        cell: Compute<T>,
+        mut callback: F,
+    ) -> Option<CallbackID> {
+        self.value(cell)?;
+        self.cells.add_callback(cell.id, move |value| {
+            callback(value.downcast().expect("cell types never change"))
+        })
+    }
+
+    pub fn remove_callback<T>(
+        &mut self,
+        cell: Compute<T>,
+        callback: CallbackID,
+    ) -> Result<(), RemoveCallbackError> {
+        self.cells.remove_callback(cell.id, callback)
+    }
+}
@@ -163,7 +163,7 @@ fn compute_cells_fire_callbacks() {
         .create_compute(&[CellID::Input(input)], |v| v[0] + 1)
         .unwrap();
     assert!(reactor
-        .add_callback(output, |v| cb.callback_called(v))
+        .add_callback(output, |&v| cb.callback_called(v))
         .is_some());
     assert!(reactor.set_value(input, 3));
     cb.expect_to_have_been_called_with(4);
@@ -177,7 +177,7 @@ fn error_adding_callback_to_nonexistent_cell() {
         .create_compute(&[CellID::Input(input)], |_| 0)
         .unwrap();
     assert_eq!(
-        Reactor::new().add_callback(output, |_: u32| println!("hi")),
+        Reactor::new().add_callback(output, |_: &u32| println!("hi")),
         None
     );
 }
@@ -190,11 +190,11 @@ fn callbacks_only_fire_on_change() {
     let output = reactor
         .create_compute(
             &[CellID::Input(input)],
-            |v| if v[0] < 3 { 111 } else { 222 },
+            |v| if *v[0] < 3 { 111 } else { 222 },
         )
         .unwrap();
     assert!(reactor
-        .add_callback(output, |v| cb.callback_called(v))
+        .add_callback(output, |&v| cb.callback_called(v))
         .is_some());
 
     assert!(reactor.set_value(input, 2));
@@ -212,7 +212,7 @@ fn callbacks_can_be_called_multiple_times() {
         .create_compute(&[CellID::Input(input)], |v| v[0] + 1)
         .unwrap();
     assert!(reactor
-        .add_callback(output, |v| cb.callback_called(v))
+        .add_callback(output, |&v| cb.callback_called(v))
         .is_some());
 
     assert!(reactor.set_value(input, 2));
@@ -234,10 +234,10 @@ fn callbacks_can_be_called_from_multiple_cells() {
         .create_compute(&[CellID::Input(input)], |v| v[0] - 1)
         .unwrap();
     assert!(reactor
-        .add_callback(plus_one, |v| cb1.callback_called(v))
+        .add_callback(plus_one, |&v| cb1.callback_called(v))
         .is_some());
     assert!(reactor
-        .add_callback(minus_one, |v| cb2.callback_called(v))
+        .add_callback(minus_one, |&v| cb2.callback_called(v))
         .is_some());
 
     assert!(reactor.set_value(input, 10));
@@ -258,10 +258,10 @@ fn callbacks_can_be_added_and_removed() {
         .unwrap();
 
     let callback = reactor
-        .add_callback(output, |v| cb1.callback_called(v))
+        .add_callback(output, |&v| cb1.callback_called(v))
         .unwrap();
     assert!(reactor
-        .add_callback(output, |v| cb2.callback_called(v))
+        .add_callback(output, |&v| cb2.callback_called(v))
         .is_some());
 
     assert!(reactor.set_value(input, 31));
@@ -270,7 +270,7 @@ fn callbacks_can_be_added_and_removed() {
 
     assert!(reactor.remove_callback(output, callback).is_ok());
     assert!(reactor
-        .add_callback(output, |v| cb3.callback_called(v))
+        .add_callback(output, |&v| cb3.callback_called(v))
         .is_some());
 
     assert!(reactor.set_value(input, 41));
@@ -290,10 +290,10 @@ fn removing_a_callback_multiple_times_doesnt_interfere_with_other_callbacks() {
         .create_compute(&[CellID::Input(input)], |v| v[0] + 1)
         .unwrap();
     let callback = reactor
-        .add_callback(output, |v| cb1.callback_called(v))
+        .add_callback(output, |&v| cb1.callback_called(v))
         .unwrap();
     assert!(reactor
-        .add_callback(output, |v| cb2.callback_called(v))
+        .add_callback(output, |&v| cb2.callback_called(v))
         .is_some());
     // We want the first remove to be Ok, but the others should be errors.
     assert!(reactor.remove_callback(output, callback).is_ok());
@@ -330,7 +330,7 @@ fn callbacks_should_only_be_called_once_even_if_multiple_dependencies_change() {
         )
         .unwrap();
     assert!(reactor
-        .add_callback(output, |v| cb.callback_called(v))
+        .add_callback(output, |&v| cb.callback_called(v))
         .is_some());
     assert!(reactor.set_value(input, 4));
     cb.expect_to_have_been_called_with(10);
@@ -354,7 +354,7 @@ fn callbacks_should_not_be_called_if_dependencies_change_but_output_value_doesnt
         )
         .unwrap();
     assert!(reactor
-        .add_callback(always_two, |v| cb.callback_called(v))
+        .add_callback(always_two, |&v| cb.callback_called(v))
         .is_some());
     for i in 2..5 {
         assert!(reactor.set_value(input, i));
@@ -382,16 +382,16 @@ fn test_adder_with_boolean_values() {
 
     let a_xor_b_and_cin = reactor
         .create_compute(&[CellID::Compute(a_xor_b), CellID::Input(carry_in)], |v| {
-            v[0] && v[1]
+            *v[0] && *v[1]
         })
         .unwrap();
     let a_and_b = reactor
-        .create_compute(&[CellID::Input(a), CellID::Input(b)], |v| v[0] && v[1])
+        .create_compute(&[CellID::Input(a), CellID::Input(b)], |v| *v[0] && *v[1])
         .unwrap();
     let carry_out = reactor
         .create_compute(
             &[CellID::Compute(a_xor_b_and_cin), CellID::Compute(a_and_b)],
-            |v| v[0] || v[1],
+            |v| *v[0] || *v[1],
         )
         .unwrap();
 
@@ -418,3 +418,799 @@ fn test_adder_with_boolean_values() {
         );
     }
 }
+
+fn counting<F: Fn(&[&i32]) -> i32>(
+    counter: &std::rc::Rc<std::cell::Cell<usize>>,
+    f: F,
+) -> impl Fn(&[&i32]) -> i32 {
+    let counter = counter.clone();
+    move |v| {
+        counter.set(counter.get() + 1);
+        f(v)
+    }
+}
+
+#[test]
+fn cells_are_recomputed_once_per_update_in_a_diamond() {
+    let count = std::rc::Rc::new(std::cell::Cell::new(0));
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let left = reactor
+        .create_compute(&[CellID::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    let right = reactor
+        .create_compute(&[CellID::Input(input)], |v| v[0] * 2)
+        .unwrap();
+    let bottom = reactor
+        .create_compute(
+            &[CellID::Compute(left), CellID::Compute(right)],
+            counting(&count, |v| v[0] + v[1]),
+        )
+        .unwrap();
+    count.set(0);
+    assert!(reactor.set_value(input, 5));
+    assert_eq!(count.get(), 1);
+    assert_eq!(reactor.value(CellID::Compute(bottom)), Some(16));
+}
+
+#[test]
+fn unchanged_cells_do_not_recompute_their_dependents() {
+    let count = std::rc::Rc::new(std::cell::Cell::new(0));
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let is_positive = reactor
+        .create_compute(&[CellID::Input(input)], |v| (*v[0] > 0) as i32)
+        .unwrap();
+    let output = reactor
+        .create_compute(&[CellID::Compute(is_positive)], counting(&count, |v| *v[0]))
+        .unwrap();
+    count.set(0);
+    assert!(reactor.set_value(input, 2));
+    assert!(reactor.set_value(input, 2));
+    assert_eq!(count.get(), 0);
+    assert!(reactor.set_value(input, -2));
+    assert_eq!(count.get(), 1);
+    assert_eq!(reactor.value(CellID::Compute(output)), Some(0));
+}
+
+#[test]
+fn updates_only_touch_affected_cells() {
+    let count = std::rc::Rc::new(std::cell::Cell::new(0));
+    let mut reactor = Reactor::new();
+    let a = reactor.create_input(1);
+    let b = reactor.create_input(1);
+    for _ in 0..100 {
+        reactor
+            .create_compute(&[CellID::Input(a)], counting(&count, |v| *v[0]))
+            .unwrap();
+    }
+    let from_b = reactor
+        .create_compute(&[CellID::Input(b)], |v| v[0] + 1)
+        .unwrap();
+    count.set(0);
+    assert!(reactor.set_value(b, 2));
+    assert_eq!(count.get(), 0);
+    assert_eq!(reactor.value(CellID::Compute(from_b)), Some(3));
+}
+
+#[test]
+fn long_chains_propagate_to_the_end() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(0);
+    let mut last = CellID::Input(input);
+    for _ in 0..10_000 {
+        last = CellID::Compute(reactor.create_compute(&[last], |v| v[0] + 1).unwrap());
+    }
+    assert_eq!(reactor.value(last), Some(10_000));
+    assert!(reactor.set_value(input, 5));
+    assert_eq!(reactor.value(last), Some(10_005));
+}
+
+#[test]
+fn long_lazy_chains_are_read_without_recursing() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(0);
+    let mut last = CellID::Input(input);
+    for _ in 0..10_000 {
+        last = CellID::Compute(reactor.create_lazy_compute(&[last], |v| v[0] + 1).unwrap());
+    }
+    assert_eq!(reactor.value(last), Some(10_000));
+    assert!(reactor.set_value(input, 5));
+    assert_eq!(reactor.value(last), Some(10_005));
+}
+
+#[test]
+fn batched_updates_fire_callbacks_once_with_the_final_value() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let a = reactor.create_input(1);
+    let b = reactor.create_input(2);
+    let sum = reactor
+        .create_compute(&[CellID::Input(a), CellID::Input(b)], |v| v[0] + v[1])
+        .unwrap();
+    assert!(reactor
+        .add_callback(sum, |&v| cb.callback_called(v))
+        .is_some());
+
+    reactor.batch(|tx| {
+        assert!(tx.set_value(a, 10));
+        assert!(tx.set_value(b, 20));
+        assert!(tx.set_value(a, 30));
+    });
+    cb.expect_to_have_been_called_with(50);
+    assert_eq!(reactor.value(CellID::Compute(sum)), Some(50));
+}
+
+#[test]
+fn batches_that_restore_values_do_not_fire_callbacks() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let a = reactor.create_input(1);
+    let b = reactor.create_input(2);
+    let difference = reactor
+        .create_compute(&[CellID::Input(a), CellID::Input(b)], |v| v[0] - v[1])
+        .unwrap();
+    assert!(reactor
+        .add_callback(difference, |&v| cb.callback_called(v))
+        .is_some());
+
+    reactor.batch(|tx| {
+        assert!(tx.set_value(a, 5));
+        assert!(tx.set_value(b, 6));
+    });
+    cb.expect_not_to_have_been_called();
+}
+
+#[test]
+fn batches_return_the_closure_result() {
+    let mut dummy_reactor = Reactor::new();
+    let _ = dummy_reactor.create_input(1);
+    let missing = dummy_reactor.create_input(2);
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor
+        .create_compute(&[CellID::Input(input)], |v| v[0] * 2)
+        .unwrap();
+    let results = reactor.batch(|tx| (tx.set_value(input, 4), tx.set_value(missing, 4)));
+    assert_eq!(results, (true, false));
+    assert_eq!(reactor.value(CellID::Compute(output)), Some(8));
+}
+
+#[test]
+fn cells_can_be_removed() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor
+        .create_compute(&[CellID::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    assert_eq!(reactor.remove_cell(CellID::Compute(output)), Ok(()));
+    assert_eq!(reactor.value(CellID::Compute(output)), None);
+    assert_eq!(reactor.remove_cell(CellID::Input(input)), Ok(()));
+    assert_eq!(reactor.value(CellID::Input(input)), None);
+    assert!(!reactor.set_value(input, 2));
+}
+
+#[test]
+fn error_removing_a_cell_with_dependents() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let plus_one = reactor
+        .create_compute(&[CellID::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    let times_two = reactor
+        .create_compute(&[CellID::Input(input)], |v| v[0] * 2)
+        .unwrap();
+    let error = reactor.remove_cell(CellID::Input(input)).unwrap_err();
+    assert_eq!(
+        error,
+        RemoveCellError::HasDependents(vec![plus_one, times_two])
+    );
+    assert!(error.to_string().contains("2 compute cell(s)"));
+
+    assert!(reactor.remove_cell(CellID::Compute(plus_one)).is_ok());
+    assert_eq!(
+        reactor.remove_cell(CellID::Input(input)),
+        Err(RemoveCellError::HasDependents(vec![times_two]))
+    );
+    assert!(reactor.set_value(input, 5));
+    assert_eq!(reactor.value(CellID::Compute(times_two)), Some(10));
+}
+
+#[test]
+fn error_removing_a_cell_twice() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    assert!(reactor.remove_cell(CellID::Input(input)).is_ok());
+    assert_eq!(
+        reactor.remove_cell(CellID::Input(input)),
+        Err(RemoveCellError::NonexistentCell)
+    );
+}
+
+#[test]
+fn stale_ids_do_not_refer_to_cells_reusing_their_slot() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor
+        .create_compute(&[CellID::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    assert!(reactor.remove_cell(CellID::Compute(output)).is_ok());
+    assert!(reactor.remove_cell(CellID::Input(input)).is_ok());
+
+    let new_input = reactor.create_input(10);
+    let new_output = reactor
+        .create_compute(&[CellID::Input(new_input)], |v| v[0] * 2)
+        .unwrap();
+    assert_eq!(reactor.value(CellID::Input(input)), None);
+    assert_eq!(reactor.value(CellID::Compute(output)), None);
+    assert!(!reactor.set_value(input, 3));
+    assert_eq!(reactor.add_callback(output, |_| ()), None);
+    assert_eq!(
+        reactor.create_compute(&[CellID::Compute(output)], |v| *v[0]),
+        Err(CellID::Compute(output))
+    );
+    assert_eq!(reactor.value(CellID::Compute(new_output)), Some(20));
+}
+
+#[test]
+fn removed_cells_are_no_longer_updated() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let kept = reactor
+        .create_compute(&[CellID::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    let removed = reactor
+        .create_compute(&[CellID::Input(input)], |v| v[0] - 1)
+        .unwrap();
+    assert!(reactor
+        .add_callback(removed, |&v| cb.callback_called(v))
+        .is_some());
+    assert!(reactor.remove_cell(CellID::Compute(removed)).is_ok());
+    assert!(reactor.set_value(input, 5));
+    cb.expect_not_to_have_been_called();
+    assert_eq!(reactor.value(CellID::Compute(kept)), Some(6));
+}
+
+#[test]
+fn cells_can_hold_values_that_are_not_copy() {
+    let mut reactor = Reactor::new();
+    let first = reactor.create_input(String::from("Hello"));
+    let last = reactor.create_input(String::from("World"));
+    let greeting = reactor
+        .create_compute(&[CellID::Input(first), CellID::Input(last)], |v| {
+            format!("{}, {}!", v[0], v[1])
+        })
+        .unwrap();
+    let length = reactor
+        .create_compute(&[CellID::Compute(greeting)], |v| v[0].len().to_string())
+        .unwrap();
+    assert_eq!(
+        reactor
+            .value_ref(CellID::Compute(greeting))
+            .map(String::as_str),
+        Some("Hello, World!")
+    );
+    assert!(reactor.set_value(last, String::from("Reactor")));
+    assert_eq!(
+        reactor.value(CellID::Compute(greeting)),
+        Some(String::from("Hello, Reactor!"))
+    );
+    assert_eq!(
+        reactor.value(CellID::Compute(length)),
+        Some(String::from("15"))
+    );
+}
+
+#[test]
+fn callbacks_receive_borrowed_values() {
+    let seen = std::cell::RefCell::new(vec![]);
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(vec![1, 2]);
+    let doubled = reactor
+        .create_compute(&[CellID::Input(input)], |v| {
+            v[0].iter().map(|x| x * 2).collect::<Vec<_>>()
+        })
+        .unwrap();
+    assert!(reactor
+        .add_callback(doubled, |v: &Vec<i32>| seen.borrow_mut().push(v.clone()))
+        .is_some());
+    assert!(reactor.set_value(input, vec![3]));
+    assert!(reactor.set_value(input, vec![3]));
+    assert!(reactor.set_value(input, vec![4, 5]));
+    assert_eq!(*seen.borrow(), vec![vec![6], vec![8, 10]]);
+}
+
+#[test]
+fn typed_reactors_hold_cells_of_different_types() {
+    let mut reactor = TypedReactor::new();
+    let price = reactor.create_input(2.5);
+    let quantity = reactor.create_input(4u32);
+    let name = reactor.create_input(String::from("apples"));
+    let total = reactor
+        .create_compute((price, quantity), |(p, q)| p * f64::from(*q))
+        .unwrap();
+    let label = reactor
+        .create_compute((name, quantity, total), |(n, q, t)| {
+            format!("{} {}: {:.2}", q, n, t)
+        })
+        .unwrap();
+    assert_eq!(reactor.value(total), Some(&10.0));
+    assert_eq!(
+        reactor.value(label).map(String::as_str),
+        Some("4 apples: 10.00")
+    );
+
+    assert!(reactor.set_value(quantity, 3));
+    assert!(reactor.set_value(name, String::from("pears")));
+    assert_eq!(
+        reactor.value(label).map(String::as_str),
+        Some("3 pears: 7.50")
+    );
+}
+
+#[test]
+fn typed_callbacks_receive_typed_values() {
+    let seen = std::cell::RefCell::new(vec![]);
+    let mut reactor = TypedReactor::new();
+    let input = reactor.create_input(1);
+    let is_even = reactor.create_compute((input,), |(v,)| v % 2 == 0).unwrap();
+    let callback = reactor
+        .add_callback(is_even, |&even: &bool| seen.borrow_mut().push(even))
+        .unwrap();
+    assert!(reactor.set_value(input, 2));
+    assert!(reactor.set_value(input, 4));
+    assert!(reactor.set_value(input, 5));
+    assert_eq!(reactor.remove_callback(is_even, callback), Ok(()));
+    assert!(reactor.set_value(input, 6));
+    assert_eq!(*seen.borrow(), vec![true, false]);
+}
+
+#[test]
+fn typed_handles_from_another_reactor_are_rejected() {
+    let mut other = TypedReactor::new();
+    let foreign = other.create_input(String::from("text"));
+    let mut reactor = TypedReactor::new();
+    let number = reactor.create_input(1);
+    assert_eq!(reactor.value(foreign), None);
+    assert!(!reactor.set_value(foreign, String::from("more text")));
+    assert_eq!(
+        reactor
+            .create_compute((number, foreign), |(n, s)| s.len() + *n as usize)
+            .unwrap_err(),
+        foreign.id()
+    );
+}
+
+#[test]
+fn subscriptions_receive_changes_on_another_thread() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor
+        .create_compute(&[CellID::Input(input)], |v| v[0] * 10)
+        .unwrap();
+    let changes = reactor.subscribe(output).unwrap();
+    let observer = std::thread::spawn(move || changes.iter().collect::<Vec<_>>());
+
+    assert!(reactor.set_value(input, 2));
+    assert!(reactor.set_value(input, 2));
+    assert!(reactor.set_value(input, 3));
+    drop(reactor);
+    assert_eq!(observer.join().unwrap(), vec![20, 30]);
+}
+
+#[test]
+fn dropped_subscriptions_do_not_affect_others() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor
+        .create_compute(&[CellID::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    let dropped = reactor.subscribe(output).unwrap();
+    let kept = reactor.subscribe(output).unwrap();
+    drop(dropped);
+    assert!(reactor.set_value(input, 5));
+    assert_eq!(kept.try_recv(), Ok(6));
+    assert!(kept.try_recv().is_err());
+}
+
+#[test]
+fn error_subscribing_to_a_nonexistent_cell() {
+    let mut dummy_reactor = Reactor::new();
+    let input = dummy_reactor.create_input(1);
+    let output = dummy_reactor
+        .create_compute(&[CellID::Input(input)], |_| 0)
+        .unwrap();
+    assert!(Reactor::<i32>::new().subscribe(output).is_none());
+}
+
+#[test]
+fn sync_reactors_are_consistent_under_concurrent_updates() {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    let reactor = Arc::new(SyncReactor::new());
+    let a = reactor.create_input(0i64);
+    let b = reactor.create_input(0i64);
+    let sum = reactor
+        .create_compute(&[CellID::Input(a), CellID::Input(b)], |v| v[0] + v[1])
+        .unwrap();
+    let difference = reactor
+        .create_compute(&[CellID::Input(a), CellID::Input(b)], |v| v[0] - v[1])
+        .unwrap();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    assert!(reactor
+        .add_callback(sum, move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        })
+        .is_some());
+
+    let writers = (1..=4)
+        .map(|thread| {
+            let reactor = reactor.clone();
+            std::thread::spawn(move || {
+                for i in 0..500 {
+                    let value = thread * 1000 + i;
+                    reactor.batch(|tx| {
+                        assert!(tx.set_value(a, value));
+                        assert!(tx.set_value(b, value));
+                    });
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    let readers = (0..4)
+        .map(|_| {
+            let reactor = reactor.clone();
+            std::thread::spawn(move || {
+                for _ in 0..500 {
+                    let (a, sum, difference) = reactor.read(|r| {
+                        (
+                            r.value(CellID::Input(a)).unwrap(),
+                            r.value(CellID::Compute(sum)).unwrap(),
+                            r.value(CellID::Compute(difference)).unwrap(),
+                        )
+                    });
+                    assert_eq!(sum, 2 * a);
+                    assert_eq!(difference, 0);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in writers.into_iter().chain(readers) {
+        thread.join().unwrap();
+    }
+
+    assert_eq!(calls.load(Ordering::SeqCst), 2000);
+    assert_eq!(reactor.value(CellID::Compute(difference)), Some(0));
+}
+
+#[test]
+fn cells_can_be_listed_and_inspected() {
+    let mut reactor = Reactor::new();
+    let a = reactor.create_input(1);
+    let b = reactor.create_input(2);
+    let sum = reactor
+        .create_compute(&[CellID::Input(a), CellID::Input(b)], |v| v[0] + v[1])
+        .unwrap();
+    let double = reactor
+        .create_compute(&[CellID::Compute(sum)], |v| v[0] * 2)
+        .unwrap();
+    assert!(reactor.add_callback(sum, |_| ()).is_some());
+    assert_eq!(
+        reactor.cells(),
+        vec![
+            CellID::Input(a),
+            CellID::Input(b),
+            CellID::Compute(sum),
+            CellID::Compute(double)
+        ]
+    );
+    assert_eq!(
+        reactor.inspect(CellID::Input(a)),
+        Some(CellInfo {
+            id: CellID::Input(a),
+            value: Ok(&1),
+            dependencies: &[],
+            dependents: &[sum],
+            callbacks: 0,
+            recomputed: None,
+        })
+    );
+    assert_eq!(
+        reactor.inspect(CellID::Compute(sum)),
+        Some(CellInfo {
+            id: CellID::Compute(sum),
+            value: Ok(&3),
+            dependencies: &[CellID::Input(a), CellID::Input(b)],
+            dependents: &[double],
+            callbacks: 1,
+            recomputed: Some(0),
+        })
+    );
+
+    assert!(reactor.remove_cell(CellID::Compute(double)).is_ok());
+    assert_eq!(reactor.inspect(CellID::Compute(double)), None);
+    assert_eq!(reactor.cells().len(), 3);
+}
+
+#[test]
+fn inspection_reports_when_cells_were_last_recomputed() {
+    let mut reactor = Reactor::new();
+    let a = reactor.create_input(1);
+    let b = reactor.create_input(1);
+    let from_a = reactor
+        .create_compute(&[CellID::Input(a)], |v| v[0] + 1)
+        .unwrap();
+    let from_b = reactor
+        .create_compute(&[CellID::Input(b)], |v| v[0] + 1)
+        .unwrap();
+    let recomputed =
+        |reactor: &Reactor<i32>, id| reactor.inspect(CellID::Compute(id)).unwrap().recomputed;
+
+    assert_eq!(reactor.generation(), 0);
+    assert!(reactor.set_value(a, 2));
+    assert!(reactor.set_value(a, 2));
+    assert!(reactor.set_value(b, 3));
+    assert_eq!(reactor.generation(), 2);
+    assert_eq!(recomputed(&reactor, from_a), Some(1));
+    assert_eq!(recomputed(&reactor, from_b), Some(2));
+}
+
+#[test]
+fn reactors_render_as_dot_graphs() {
+    let mut reactor = Reactor::new();
+    let name = reactor.create_input("a \"quoted\" name");
+    let size = reactor
+        .create_compute(&[CellID::Input(name)], |v| {
+            if v[0].len() > 10 {
+                "long"
+            } else {
+                "short"
+            }
+        })
+        .unwrap();
+    assert!(reactor.add_callback(size, |_| ()).is_some());
+    assert_eq!(
+        reactor.to_dot(),
+        r#"digraph reactor {
+    input0 [shape=box, label="input0 = \"a \\\"quoted\\\" name\""];
+    compute0 [shape=ellipse, label="compute0 = \"long\"\ncallbacks: 1"];
+    input0 -> compute0 [label="0"];
+}
+"#
+    );
+}
+
+fn checked_div(v: &[&i32]) -> Result<i32, String> {
+    v[0].checked_div(*v[1])
+        .ok_or_else(|| format!("cannot divide {} by {}", v[0], v[1]))
+}
+
+#[test]
+fn fallible_compute_cells_expose_errors() {
+    let mut reactor = Reactor::default();
+    let a = reactor.create_input(10);
+    let b = reactor.create_input(2);
+    let quotient = reactor
+        .create_compute_fallible(&[CellID::Input(a), CellID::Input(b)], checked_div)
+        .unwrap();
+    assert_eq!(reactor.result(CellID::Compute(quotient)), Some(Ok(&5)));
+
+    assert!(reactor.set_value(b, 0));
+    assert_eq!(
+        reactor.result(CellID::Compute(quotient)),
+        Some(Err(&String::from("cannot divide 10 by 0")))
+    );
+    assert_eq!(reactor.value(CellID::Compute(quotient)), None);
+
+    assert!(reactor.set_value(b, 5));
+    assert_eq!(reactor.value(CellID::Compute(quotient)), Some(2));
+}
+
+#[test]
+fn errors_propagate_to_dependents() {
+    let count = std::rc::Rc::new(std::cell::Cell::new(0));
+    let mut reactor = Reactor::default();
+    let a = reactor.create_input(10);
+    let b = reactor.create_input(2);
+    let quotient = reactor
+        .create_compute_fallible(&[CellID::Input(a), CellID::Input(b)], checked_div)
+        .unwrap();
+    let plus_one = reactor
+        .create_compute(&[CellID::Compute(quotient)], counting(&count, |v| v[0] + 1))
+        .unwrap();
+    let sum = reactor
+        .create_compute(&[CellID::Input(a), CellID::Compute(plus_one)], |v| {
+            v[0] + v[1]
+        })
+        .unwrap();
+
+    count.set(0);
+    assert!(reactor.set_value(b, 0));
+    assert_eq!(count.get(), 0);
+    let error = String::from("cannot divide 10 by 0");
+    assert_eq!(reactor.result(CellID::Compute(plus_one)), Some(Err(&error)));
+    assert_eq!(reactor.result(CellID::Compute(sum)), Some(Err(&error)));
+
+    assert!(reactor.set_value(b, 10));
+    assert_eq!(reactor.value(CellID::Compute(sum)), Some(12));
+}
+
+#[test]
+fn change_callbacks_distinguish_errors_from_values() {
+    let changes = std::cell::RefCell::new(vec![]);
+    let values = std::cell::RefCell::new(vec![]);
+    let mut reactor = Reactor::default();
+    let a = reactor.create_input(10);
+    let b = reactor.create_input(2);
+    let quotient = reactor
+        .create_compute_fallible(&[CellID::Input(a), CellID::Input(b)], checked_div)
+        .unwrap();
+    assert!(reactor
+        .add_change_callback(quotient, |change| changes
+            .borrow_mut()
+            .push(format!("{:?}", change)))
+        .is_some());
+    assert!(reactor
+        .add_callback(quotient, |&v| values.borrow_mut().push(v))
+        .is_some());
+
+    assert!(reactor.set_value(b, 0));
+    assert!(reactor.set_value(a, 20));
+    assert!(reactor.set_value(a, 10));
+    assert!(reactor.set_value(b, 2));
+    assert!(reactor.set_value(b, 1));
+    assert_eq!(
+        *changes.borrow(),
+        vec![
+            "Error(\"cannot divide 10 by 0\")",
+            "Error(\"cannot divide 20 by 0\")",
+            "Error(\"cannot divide 10 by 0\")",
+            "Recovered(5)",
+            "Value(10)",
+        ]
+    );
+    assert_eq!(*values.borrow(), vec![5, 10]);
+}
+
+#[test]
+fn lazy_cells_are_computed_when_read() {
+    let count = std::rc::Rc::new(std::cell::Cell::new(0));
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor
+        .create_lazy_compute(&[CellID::Input(input)], counting(&count, |v| v[0] * 2))
+        .unwrap();
+    assert_eq!(count.get(), 0);
+    assert_eq!(reactor.value(CellID::Compute(output)), Some(2));
+    assert_eq!(reactor.value(CellID::Compute(output)), Some(2));
+    assert_eq!(count.get(), 1);
+
+    assert!(reactor.set_value(input, 2));
+    assert!(reactor.set_value(input, 3));
+    assert!(reactor.set_value(input, 4));
+    assert_eq!(count.get(), 1);
+    assert_eq!(reactor.value(CellID::Compute(output)), Some(8));
+    assert_eq!(count.get(), 2);
+}
+
+#[test]
+fn eager_cells_read_their_lazy_dependencies() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let lazy = reactor
+        .create_lazy_compute(&[CellID::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    let eager = reactor
+        .create_compute(&[CellID::Compute(lazy)], |v| v[0] * 10)
+        .unwrap();
+    assert_eq!(reactor.value(CellID::Compute(eager)), Some(20));
+    assert!(reactor.set_value(input, 4));
+    assert_eq!(reactor.value(CellID::Compute(eager)), Some(50));
+}
+
+#[test]
+fn memoized_cells_spare_their_dependents_if_unchanged() {
+    let count = std::rc::Rc::new(std::cell::Cell::new(0));
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let sign = reactor
+        .create_memoized_compute(&[CellID::Input(input)], |v: &[&i32]| v[0].signum())
+        .unwrap();
+    let output = reactor
+        .create_memoized_compute(&[CellID::Compute(sign)], counting(&count, |v| v[0] * 100))
+        .unwrap();
+    assert_eq!(reactor.value(CellID::Compute(output)), Some(100));
+
+    assert!(reactor.set_value(input, 5));
+    assert_eq!(reactor.value(CellID::Compute(output)), Some(100));
+    assert_eq!(count.get(), 1);
+
+    assert!(reactor.set_value(input, -5));
+    assert_eq!(reactor.value(CellID::Compute(output)), Some(-100));
+    assert_eq!(count.get(), 2);
+}
+
+#[test]
+fn memoized_cells_stay_correct_when_read_at_different_times() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let a = reactor
+        .create_memoized_compute(&[CellID::Input(input)], |v| v[0] / 2)
+        .unwrap();
+    let b = reactor
+        .create_memoized_compute(&[CellID::Compute(a)], |v| v[0] + 1)
+        .unwrap();
+    assert_eq!(reactor.value(CellID::Compute(b)), Some(1));
+
+    assert!(reactor.set_value(input, 4));
+    assert_eq!(reactor.value(CellID::Compute(a)), Some(2));
+    assert!(reactor.set_value(input, 5));
+    assert_eq!(reactor.value(CellID::Compute(b)), Some(3));
+    assert!(reactor.set_value(input, 0));
+    assert!(reactor.set_value(input, 1));
+    assert_eq!(reactor.value(CellID::Compute(b)), Some(1));
+}
+
+#[test]
+fn lazy_cells_with_callbacks_are_computed_on_change() {
+    let count = std::rc::Rc::new(std::cell::Cell::new(0));
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor
+        .create_lazy_compute(&[CellID::Input(input)], counting(&count, |v| v[0] + 1))
+        .unwrap();
+    let callback = reactor
+        .add_callback(output, |&v| cb.callback_called(v))
+        .unwrap();
+    assert_eq!(count.get(), 1);
+
+    assert!(reactor.set_value(input, 3));
+    cb.expect_to_have_been_called_with(4);
+    assert_eq!(count.get(), 2);
+
+    assert!(reactor.remove_callback(output, callback).is_ok());
+    assert!(reactor.set_value(input, 5));
+    assert_eq!(count.get(), 2);
+    assert_eq!(reactor.value(CellID::Compute(output)), Some(6));
+}
+
+#[test]
+fn unchanged_memoized_cells_spare_lazy_and_eager_dependents() {
+    let lazy_count = std::rc::Rc::new(std::cell::Cell::new(0));
+    let eager_count = std::rc::Rc::new(std::cell::Cell::new(0));
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let sign = reactor
+        .create_memoized_compute(&[CellID::Input(input)], |v: &[&i32]| v[0].signum())
+        .unwrap();
+    let lazy = reactor
+        .create_lazy_compute(
+            &[CellID::Compute(sign)],
+            counting(&lazy_count, |v| v[0] * 10),
+        )
+        .unwrap();
+    let eager = reactor
+        .create_compute(
+            &[CellID::Compute(sign)],
+            counting(&eager_count, |v| v[0] * 100),
+        )
+        .unwrap();
+    assert_eq!(reactor.value(CellID::Compute(lazy)), Some(10));
+    assert_eq!((lazy_count.get(), eager_count.get()), (1, 1));
+
+    assert!(reactor.set_value(input, 5));
+    assert_eq!(reactor.value(CellID::Compute(lazy)), Some(10));
+    assert_eq!(reactor.value(CellID::Compute(eager)), Some(100));
+    assert_eq!((lazy_count.get(), eager_count.get()), (1, 1));
+
+    assert!(reactor.set_value(input, -5));
+    assert_eq!(reactor.value(CellID::Compute(lazy)), Some(-10));
+    assert_eq!(reactor.value(CellID::Compute(eager)), Some(-100));
+    assert_eq!((lazy_count.get(), eager_count.get()), (2, 2));
+}
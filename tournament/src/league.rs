@@ -0,0 +1,164 @@
+use crate::{rank, record, table, LeagueRules, Match, ParseErrorKind, Row, Score, Standings};
+use std::collections::HashMap;
+use std::fmt;
+
+/// The outcome of a match for one of its teams.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Outcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+/// Shows the outcome as `W`, `D` or `L`, as in form guides.
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Outcome::Win => "W",
+            Outcome::Draw => "D",
+            Outcome::Loss => "L",
+        })
+    }
+}
+
+/// Where a team stands after a round, and where it stood before it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Movement {
+    pub team: String,
+    /// The position in the table, starting at 1.
+    pub position: usize,
+    /// `None` if the team played its first match in the round.
+    pub previous: Option<usize>,
+}
+
+/// A league whose matches are added one at a time, in rounds, keeping a history of the table.
+///
+/// ```
+/// use tournament::League;
+///
+/// let mut league = League::default();
+/// league.add_match("Allegoric Alaskans;Blithering Badgers;win").unwrap();
+/// league.end_round();
+/// league.add_match("Blithering Badgers;Courageous Californians;3-0").unwrap();
+/// assert_eq!(league.rounds(), 2);
+/// assert_eq!(league.standings_after(1).unwrap().to_csv().lines().count(), 3);
+///
+/// let form = league.form("Blithering Badgers");
+/// assert_eq!(form.iter().map(ToString::to_string).collect::<String>(), "LW");
+/// ```
+#[derive(Debug, Default)]
+pub struct League {
+    rules: LeagueRules,
+    matches: Vec<Match>,
+    // the number of matches played by the end of each finished round
+    finished: Vec<usize>,
+    // the rows after all matches so far
+    rows: HashMap<String, Row>,
+}
+
+impl League {
+    pub fn new(rules: LeagueRules) -> Self {
+        League {
+            rules,
+            ..League::default()
+        }
+    }
+
+    /// Adds a match in the format of a line of `tally` input to the current round.
+    pub fn add_match(&mut self, result: &str) -> Result<(), ParseErrorKind> {
+        let m = result.parse::<Match>()?;
+        record(&mut self.rows, &m, &self.rules);
+        self.matches.push(m);
+        Ok(())
+    }
+
+    /// Finishes the current round, so further matches are added to the next one. Does nothing if
+    /// no match was added since the last round.
+    pub fn end_round(&mut self) {
+        if self.matches.len() > self.played_by(self.finished.len()) {
+            self.finished.push(self.matches.len());
+        }
+    }
+
+    /// Returns the number of rounds with matches, including the current one.
+    pub fn rounds(&self) -> usize {
+        if self.matches.len() > self.played_by(self.finished.len()) {
+            self.finished.len() + 1
+        } else {
+            self.finished.len()
+        }
+    }
+
+    // Returns the number of matches played by the end of the given round.
+    fn played_by(&self, round: usize) -> usize {
+        match round {
+            0 => 0,
+            _ => self
+                .finished
+                .get(round - 1)
+                .copied()
+                .unwrap_or(self.matches.len()),
+        }
+    }
+
+    /// Returns the table after all matches so far.
+    pub fn standings(&self) -> Standings {
+        rank(
+            self.rows.values().cloned().collect(),
+            &self.matches,
+            &self.rules,
+        )
+    }
+
+    /// Returns the table after the given round, counting from 1, or None if there is no such
+    /// round yet. The table after round 0 is empty.
+    pub fn standings_after(&self, round: usize) -> Option<Standings> {
+        if round > self.rounds() {
+            return None;
+        }
+        Some(table(&self.matches[..self.played_by(round)], &self.rules))
+    }
+
+    /// Lists the teams in their order after the given round, counting from 1, with their
+    /// positions before it. Returns None if there is no such round yet.
+    pub fn position_changes(&self, round: usize) -> Option<Vec<Movement>> {
+        let after = self.standings_after(round)?;
+        let before = self.standings_after(round.checked_sub(1)?)?;
+        let previous = before
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(index, row)| (row.team.as_str(), index + 1))
+            .collect::<HashMap<_, _>>();
+        let movements = after
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(index, row)| Movement {
+                team: row.team.clone(),
+                position: index + 1,
+                previous: previous.get(row.team.as_str()).copied(),
+            })
+            .collect();
+        Some(movements)
+    }
+
+    /// Returns the outcomes of the last five matches of the team, oldest first.
+    pub fn form(&self, team: &str) -> Vec<Outcome> {
+        let mut form = self
+            .matches
+            .iter()
+            .rev()
+            .filter_map(|m| match m.result {
+                Score::Draw if m.home == team || m.away == team => Some(Outcome::Draw),
+                Score::Home if m.home == team => Some(Outcome::Win),
+                Score::Away if m.away == team => Some(Outcome::Win),
+                _ if m.home == team || m.away == team => Some(Outcome::Loss),
+                _ => None,
+            })
+            .take(5)
+            .collect::<Vec<_>>();
+        form.reverse();
+        form
+    }
+}
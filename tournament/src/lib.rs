@@ -1,10 +1,137 @@
-use std::str::FromStr;
+mod league;
+mod standings;
+
+pub use league::{League, Movement, Outcome};
+pub use standings::Standings;
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::HashMap;
 use std::fmt;
-use std::iter::once;
-use std::collections::{HashMap};
-use std::cmp::Ordering;
+use std::str::FromStr;
+
+/// Why a line of match results was rejected.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ParseErrorKind {
+    /// The line does not have exactly three `;`-separated fields; holds the number it has.
+    FieldCount(usize),
+    /// The result is not `win`, `loss`, `draw` or a score such as `3-1`; holds the result as
+    /// written.
+    UnknownResult(String),
+    EmptyTeamName,
+    /// Both teams have the same name.
+    SelfMatch,
+}
+
+/// A rejected line of match results, with its line number (starting at 1) and text.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub text: String,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ParseErrorKind::FieldCount(count) => write!(f, "expected 3 fields, found {}", count)?,
+            ParseErrorKind::UnknownResult(result) => write!(f, "unknown result {:?}", result)?,
+            ParseErrorKind::EmptyTeamName => write!(f, "empty team name")?,
+            ParseErrorKind::SelfMatch => write!(f, "a team cannot play itself")?,
+        }
+        write!(f, " in {:?}", self.text)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// How teams are ranked among those on equal points, applied in order before falling back to
+/// alphabetical order.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Tiebreaker {
+    /// Points earned in the matches between the tied teams.
+    HeadToHead,
+    Wins,
+    /// Goals scored minus goals conceded, counting only matches recorded with a score.
+    GoalDifference,
+}
+
+impl Tiebreaker {
+    // Ranks a team among the tied `group`; higher is better.
+    fn key(self, row: &Row, group: &[String], matches: &[Match], rules: &LeagueRules) -> i64 {
+        match self {
+            Tiebreaker::HeadToHead => matches
+                .iter()
+                .filter(|m| group.contains(&m.home) && group.contains(&m.away))
+                .map(|m| {
+                    let (home, away) = rules.points(&m.result);
+                    if m.home == row.team {
+                        i64::from(home)
+                    } else if m.away == row.team {
+                        i64::from(away)
+                    } else {
+                        0
+                    }
+                })
+                .sum(),
+            Tiebreaker::Wins => i64::from(row.wins),
+            Tiebreaker::GoalDifference => row.goal_difference(),
+        }
+    }
+}
+
+/// The points awarded for each result, and the tiebreakers for teams on equal points.
+///
+/// The default is 3 points for a win, 1 for a draw and none for a loss, with ties broken
+/// alphabetically. Other rules can start from it:
+///
+/// ```
+/// use tournament::{LeagueRules, Tiebreaker};
+///
+/// let rules = LeagueRules {
+///     win: 2,
+///     tiebreakers: vec![Tiebreaker::HeadToHead],
+///     ..LeagueRules::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LeagueRules {
+    pub win: u16,
+    pub draw: u16,
+    pub loss: u16,
+    /// Added to `win` for the away team.
+    pub away_win_bonus: u16,
+    pub tiebreakers: Vec<Tiebreaker>,
+}
+
+impl Default for LeagueRules {
+    fn default() -> Self {
+        LeagueRules {
+            win: 3,
+            draw: 1,
+            loss: 0,
+            away_win_bonus: 0,
+            tiebreakers: vec![],
+        }
+    }
+}
 
-type Error = Box<std::error::Error>;
+impl LeagueRules {
+    // Returns the points of the home and away team.
+    fn points(&self, result: &Score) -> (u32, u32) {
+        let (win, draw, loss) = (
+            u32::from(self.win),
+            u32::from(self.draw),
+            u32::from(self.loss),
+        );
+        match result {
+            Score::Home => (win, loss),
+            Score::Draw => (draw, draw),
+            // widened first, so that the bonus cannot overflow
+            Score::Away => (loss, win + u32::from(self.away_win_bonus)),
+        }
+    }
+}
 
 #[derive(Debug, Eq, PartialEq)]
 enum Score {
@@ -18,143 +145,284 @@ struct Match {
     home: String,
     away: String,
     result: Score,
+    // the goals of the home and away team, if the result was given as a score
+    goals: Option<(u16, u16)>,
 }
 
-impl<'a> FromStr for Match {
-    type Err = Error;
+impl FromStr for Match {
+    type Err = ParseErrorKind;
 
-    fn from_str(input: &str) -> Result<Match, Error> {
-        if let [home, away, res] = input.trim().split(';').collect::<Vec<_>>()[..] {
-            let result = match res {
-                "win" => Score::Home,
-                "loss" => Score::Away,
-                "draw" => Score::Draw,
-                _ => return Err(Box::<std::error::Error>::from("Invalid result information"))
+    fn from_str(input: &str) -> Result<Match, ParseErrorKind> {
+        let fields = input.trim().split(';').collect::<Vec<_>>();
+        if let [home, away, res] = fields[..] {
+            let (result, goals) = match res {
+                "win" => (Score::Home, None),
+                "loss" => (Score::Away, None),
+                "draw" => (Score::Draw, None),
+                _ => match parse_goals(res) {
+                    Some((home_goals, away_goals)) => {
+                        let result = match home_goals.cmp(&away_goals) {
+                            Ordering::Greater => Score::Home,
+                            Ordering::Equal => Score::Draw,
+                            Ordering::Less => Score::Away,
+                        };
+                        (result, Some((home_goals, away_goals)))
+                    }
+                    None => return Err(ParseErrorKind::UnknownResult(res.to_string())),
+                },
             };
+            if home.trim().is_empty() || away.trim().is_empty() {
+                return Err(ParseErrorKind::EmptyTeamName);
+            }
+            if home == away {
+                return Err(ParseErrorKind::SelfMatch);
+            }
 
             Ok(Match {
                 home: home.to_string(),
                 away: away.to_string(),
                 result,
+                goals,
             })
         } else {
-            Err(Box::<std::error::Error>::from("Invalid match information"))
+            Err(ParseErrorKind::FieldCount(fields.len()))
         }
     }
 }
 
+// Parses a score such as `3-1`, home goals first.
+fn parse_goals(score: &str) -> Option<(u16, u16)> {
+    let (home, away) = score.split_once('-')?;
+    Some((parse_count(home)?, parse_count(away)?))
+}
 
-#[derive(Debug, Eq, PartialEq)]
+// Parses a number of goals, which unlike `u16::from_str` rejects a sign.
+fn parse_count(goals: &str) -> Option<u16> {
+    let goals = goals.trim();
+    if goals.is_empty() || !goals.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    goals.parse().ok()
+}
+
+// Counters are added up saturating, since a few huge scores or point values could overflow any
+// of them.
+#[derive(Debug, Clone, Eq, PartialEq)]
 struct Row {
     team: String,
-    matches: u16,
-    wins: u16,
-    draws: u16,
-    losses: u16,
-    points: u16,
+    matches: u32,
+    wins: u32,
+    draws: u32,
+    losses: u32,
+    points: u32,
+    goals_for: u32,
+    goals_against: u32,
 }
 
 impl Row {
     fn new(name: &str) -> Self {
-        Row { 
+        Row {
             team: name.to_string(),
             matches: 0,
             wins: 0,
             draws: 0,
             losses: 0,
             points: 0,
+            goals_for: 0,
+            goals_against: 0,
         }
     }
 
-    fn win(&mut self) {
-        self.matches += 1;
-        self.wins += 1;
-        self.points += 3;
+    fn win(&mut self, points: u32) {
+        self.matches = self.matches.saturating_add(1);
+        self.wins = self.wins.saturating_add(1);
+        self.points = self.points.saturating_add(points);
     }
 
-    fn draw(&mut self) {
-        self.matches += 1;
-        self.draws += 1;
-        self.points += 1;
+    fn draw(&mut self, points: u32) {
+        self.matches = self.matches.saturating_add(1);
+        self.draws = self.draws.saturating_add(1);
+        self.points = self.points.saturating_add(points);
     }
 
-    fn loss(&mut self) {
-        self.matches += 1;
-        self.losses += 1;
+    fn loss(&mut self, points: u32) {
+        self.matches = self.matches.saturating_add(1);
+        self.losses = self.losses.saturating_add(1);
+        self.points = self.points.saturating_add(points);
     }
-}
 
-impl Ord for Row {
-    fn cmp(&self, other: &Row) -> Ordering {
-        self.points.cmp(&other.points).reverse().then(self.team.cmp(&other.team))
+    fn goals(&mut self, scored: u16, conceded: u16) {
+        self.goals_for = self.goals_for.saturating_add(u32::from(scored));
+        self.goals_against = self.goals_against.saturating_add(u32::from(conceded));
     }
-}
 
-impl PartialOrd for Row {
-    fn partial_cmp(&self, other: &Row) -> Option<Ordering> {
-        Some(self.cmp(other))
+    fn goal_difference(&self) -> i64 {
+        i64::from(self.goals_for) - i64::from(self.goals_against)
     }
 }
 
+// The alternate form (`{:#}`) adds the goal columns, for tables that have them.
 impl fmt::Display for Row {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:<30} | {:>2} | {:>2} | {:>2} | {:>2} | {:>2}", self.team, self.matches, self.wins, self.draws, self.losses, self.points)
+        write!(
+            f,
+            "{:<30} | {:>2} | {:>2} | {:>2} | {:>2}",
+            self.team, self.matches, self.wins, self.draws, self.losses
+        )?;
+        if f.alternate() {
+            write!(
+                f,
+                " | {:>2} | {:>2} | {:>3}",
+                self.goals_for,
+                self.goals_against,
+                self.goal_difference()
+            )?;
+        }
+        write!(f, " | {:>2}", self.points)
     }
 }
 
+// Parses the non-blank lines of `match_results`.
+fn parse(match_results: &str) -> impl Iterator<Item = Result<Match, ParseError>> + '_ {
+    match_results
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            line.parse().map_err(|kind| ParseError {
+                line: index + 1,
+                text: line.to_string(),
+                kind,
+            })
+        })
+}
+
+/// Tallies the matches, skipping lines that cannot be parsed.
+///
+/// Results are either `win`, `loss` or `draw` for the first team, or a score such as `3-1`, first
+/// team first. Once any result is a score, the table gets goals for, goals against and goal
+/// difference columns.
 pub fn tally(match_results: &str) -> String {
+    tally_with_rules(match_results, &LeagueRules::default())
+}
+
+/// Tallies the matches like `tally`, awarding points and ranking teams by the given rules.
+pub fn tally_with_rules(match_results: &str, rules: &LeagueRules) -> String {
+    let matches = parse(match_results)
+        .filter_map(Result::ok)
+        .collect::<Vec<_>>();
+    table(&matches, rules).to_string()
+}
+
+/// Tallies the matches, or returns the first line that cannot be parsed. Blank lines are
+/// ignored.
+pub fn try_tally(match_results: &str) -> Result<String, ParseError> {
+    Ok(match_results.parse::<Standings>()?.to_string())
+}
+
+/// Tallies the matches that can be parsed, and returns the rejected lines alongside the table.
+/// Blank lines are ignored.
+pub fn tally_lenient(match_results: &str) -> (String, Vec<ParseError>) {
+    let mut matches = vec![];
+    let mut rejected = vec![];
+    for parsed in parse(match_results) {
+        match parsed {
+            Ok(m) => matches.push(m),
+            Err(error) => rejected.push(error),
+        }
+    }
+    (
+        table(&matches, &LeagueRules::default()).to_string(),
+        rejected,
+    )
+}
+
+fn table(matches: &[Match], rules: &LeagueRules) -> Standings {
     let mut table: HashMap<String, Row> = HashMap::default();
+    matches.iter().for_each(|m| record(&mut table, m, rules));
+    rank(table.into_values().collect(), matches, rules)
+}
 
-    match_results
-        .lines()
-        .filter_map(|line| line.parse().ok())
-        .for_each(|m: Match| {
-            match m.result {
-                Score::Home => {
-                    table
-                        .entry(m.home.to_string())
-                        .or_insert_with(|| Row::new(&m.home))
-                        .win();
-
-                    table
-                        .entry(m.away.to_string())
-                        .or_insert_with(|| Row::new(&m.away))
-                        .loss();
-                },
-                Score::Away => {
-                    table
-                        .entry(m.home.to_string())
-                        .or_insert_with(|| Row::new(&m.home))
-                        .loss();
-
-                    table
-                        .entry(m.away.to_string())
-                        .or_insert_with(|| Row::new(&m.away))
-                        .win();
-                }
-                Score::Draw => {
-                    table
-                        .entry(m.home.to_string())
-                        .or_insert_with(|| Row::new(&m.home))
-                        .draw();
-
-                    table
-                        .entry(m.away.to_string())
-                        .or_insert_with(|| Row::new(&m.away))
-                        .draw();
-                }
-            }
-        });
+// Adds the result of a match to the rows of both teams.
+fn record(table: &mut HashMap<String, Row>, m: &Match, rules: &LeagueRules) {
+    let (home, away) = rules.points(&m.result);
+    match m.result {
+        Score::Home => {
+            table
+                .entry(m.home.to_string())
+                .or_insert_with(|| Row::new(&m.home))
+                .win(home);
+
+            table
+                .entry(m.away.to_string())
+                .or_insert_with(|| Row::new(&m.away))
+                .loss(away);
+        }
+        Score::Away => {
+            table
+                .entry(m.home.to_string())
+                .or_insert_with(|| Row::new(&m.home))
+                .loss(home);
+
+            table
+                .entry(m.away.to_string())
+                .or_insert_with(|| Row::new(&m.away))
+                .win(away);
+        }
+        Score::Draw => {
+            table
+                .entry(m.home.to_string())
+                .or_insert_with(|| Row::new(&m.home))
+                .draw(home);
+
+            table
+                .entry(m.away.to_string())
+                .or_insert_with(|| Row::new(&m.away))
+                .draw(away);
+        }
+    }
+    if let Some((home_goals, away_goals)) = m.goals {
+        table
+            .get_mut(&m.home)
+            .expect("both teams have rows")
+            .goals(home_goals, away_goals);
+        table
+            .get_mut(&m.away)
+            .expect("both teams have rows")
+            .goals(away_goals, home_goals);
+    }
+}
 
-    let mut rows = table
-        .values()
-        .collect::<Vec<&Row>>();
-    rows.sort();
+// Orders the rows of the teams playing `matches` by points, then by the tiebreakers of the rules.
+fn rank(mut rows: Vec<Row>, matches: &[Match], rules: &LeagueRules) -> Standings {
+    rows.sort_by_key(|row| Reverse(row.points));
+    for tied in rows.chunk_by_mut(|a, b| a.points == b.points) {
+        break_ties(tied, &rules.tiebreakers, matches, rules);
+    }
 
-    let header = "Team                           | MP |  W |  D |  L |  P";
+    Standings {
+        rows,
+        goals: matches.iter().any(|m| m.goals.is_some()),
+    }
+}
 
-    once(header.to_string())
-        .chain(rows.iter().map(|r| r.to_string()))
-        .collect::<Vec<_>>()
-        .join("\n")
+// Orders rows on equal points by the first tiebreaker, then each group still tied by the next
+// ones, and finally by name.
+fn break_ties(
+    rows: &mut [Row],
+    tiebreakers: &[Tiebreaker],
+    matches: &[Match],
+    rules: &LeagueRules,
+) {
+    match tiebreakers.split_first() {
+        Some((&tiebreaker, rest)) if rows.len() > 1 => {
+            let group = rows.iter().map(|row| row.team.clone()).collect::<Vec<_>>();
+            let key = |row: &Row| tiebreaker.key(row, &group, matches, rules);
+            rows.sort_by_cached_key(|row| Reverse(key(row)));
+            for tied in rows.chunk_by_mut(|a, b| key(a) == key(b)) {
+                break_ties(tied, rest, matches, rules);
+            }
+        }
+        _ => rows.sort_by(|a, b| a.team.cmp(&b.team)),
+    }
 }
@@ -0,0 +1,212 @@
+use crate::{parse, table, LeagueRules, ParseError, Row};
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+const HEADER: &str = "Team                           | MP |  W |  D |  L |  P";
+const HEADER_WITH_GOALS: &str =
+    "Team                           | MP |  W |  D |  L | GF | GA |  GD |  P";
+
+/// The ranked rows of a league table, which can be rendered in several formats. Every format
+/// lists the teams in the same order.
+///
+/// `Display` gives the text table returned by `tally`, and the `to_*` methods give the others:
+///
+/// ```
+/// use tournament::Standings;
+///
+/// let standings = "Allegoric Alaskans;Blithering Badgers;win"
+///     .parse::<Standings>()
+///     .unwrap();
+/// assert_eq!(
+///     standings.to_csv(),
+///     "Team,MP,W,D,L,P\nAllegoric Alaskans,1,1,0,0,3\nBlithering Badgers,1,0,0,1,0\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Standings {
+    pub(crate) rows: Vec<Row>,
+    // whether any result was a score, which adds the goal columns
+    pub(crate) goals: bool,
+}
+
+/// Parses match results like `try_tally`, with the default rules.
+impl FromStr for Standings {
+    type Err = ParseError;
+
+    fn from_str(match_results: &str) -> Result<Standings, ParseError> {
+        Standings::parse_with_rules(match_results, &LeagueRules::default())
+    }
+}
+
+impl Standings {
+    /// Parses match results like `try_tally`, awarding points and ranking teams by the given
+    /// rules.
+    pub fn parse_with_rules(
+        match_results: &str,
+        rules: &LeagueRules,
+    ) -> Result<Standings, ParseError> {
+        let matches = parse(match_results).collect::<Result<Vec<_>, _>>()?;
+        Ok(table(&matches, rules))
+    }
+
+    // The abbreviated and the JSON names of the columns after the team name.
+    fn columns(&self) -> Vec<(&'static str, &'static str)> {
+        let mut columns = vec![
+            ("MP", "matches"),
+            ("W", "wins"),
+            ("D", "draws"),
+            ("L", "losses"),
+        ];
+        if self.goals {
+            columns.extend([
+                ("GF", "goals_for"),
+                ("GA", "goals_against"),
+                ("GD", "goal_difference"),
+            ]);
+        }
+        columns.push(("P", "points"));
+        columns
+    }
+
+    // The values of the columns after the team name, in the order of `columns`.
+    fn values(&self, row: &Row) -> Vec<i64> {
+        let mut values = vec![row.matches, row.wins, row.draws, row.losses]
+            .into_iter()
+            .map(i64::from)
+            .collect::<Vec<_>>();
+        if self.goals {
+            values.extend([
+                i64::from(row.goals_for),
+                i64::from(row.goals_against),
+                row.goal_difference(),
+            ]);
+        }
+        values.push(i64::from(row.points));
+        values
+    }
+
+    /// Renders the table as CSV, with a header line.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("Team");
+        for (name, _) in self.columns() {
+            write!(csv, ",{}", name).unwrap();
+        }
+        csv.push('\n');
+        for row in &self.rows {
+            csv.push_str(&csv_field(&row.team));
+            for value in self.values(row) {
+                write!(csv, ",{}", value).unwrap();
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// Renders the table as a JSON array with an object for each team.
+    pub fn to_json(&self) -> String {
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                let mut object = format!("{{\"team\":{}", json_string(&row.team));
+                for ((_, key), value) in self.columns().into_iter().zip(self.values(row)) {
+                    write!(object, ",\"{}\":{}", key, value).unwrap();
+                }
+                object.push('}');
+                object
+            })
+            .collect::<Vec<_>>();
+        format!("[{}]", rows.join(","))
+    }
+
+    /// Renders the table as a Markdown table, with the numbers aligned right.
+    pub fn to_markdown(&self) -> String {
+        let columns = self.columns();
+        let mut markdown = String::from("| Team |");
+        for (name, _) in &columns {
+            write!(markdown, " {} |", name).unwrap();
+        }
+        markdown.push_str("\n| :--- |");
+        for _ in &columns {
+            markdown.push_str(" ---: |");
+        }
+        markdown.push('\n');
+        for row in &self.rows {
+            write!(markdown, "| {} |", row.team.replace('|', "\\|")).unwrap();
+            for value in self.values(row) {
+                write!(markdown, " {} |", value).unwrap();
+            }
+            markdown.push('\n');
+        }
+        markdown
+    }
+
+    /// Renders the table as an HTML `<table>` element.
+    pub fn to_html(&self) -> String {
+        let mut html = String::from("<table>\n  <thead>\n    <tr><th>Team</th>");
+        for (name, _) in self.columns() {
+            write!(html, "<th>{}</th>", name).unwrap();
+        }
+        html.push_str("</tr>\n  </thead>\n  <tbody>\n");
+        for row in &self.rows {
+            write!(html, "    <tr><td>{}</td>", html_text(&row.team)).unwrap();
+            for value in self.values(row) {
+                write!(html, "<td>{}</td>", value).unwrap();
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("  </tbody>\n</table>\n");
+        html
+    }
+}
+
+impl fmt::Display for Standings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.goals {
+            f.write_str(HEADER_WITH_GOALS)?;
+        } else {
+            f.write_str(HEADER)?;
+        }
+        for row in &self.rows {
+            if self.goals {
+                write!(f, "\n{:#}", row)?;
+            } else {
+                write!(f, "\n{}", row)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Quotes a CSV field if it contains a separator, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn html_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -1,3 +1,6 @@
+// The upstream tests import the crate by name and borrow `&str` inputs; keep them as written.
+#![allow(clippy::needless_borrow, clippy::single_component_path_imports)]
+
 use tournament;
 
 #[test]
@@ -142,3 +145,541 @@ fn ties_broken_alphabetically() {
 
     assert_eq!(tournament::tally(&input), expected);
 }
+
+#[test]
+fn tally_skips_bad_lines() {
+    let input = "Allegoric Alaskans;Blithering Badgers;wn\n".to_string()
+        + "Allegoric Alaskans;Blithering Badgers;win";
+    let expected = "".to_string()
+        + "Team                           | MP |  W |  D |  L |  P\n"
+        + "Allegoric Alaskans             |  1 |  1 |  0 |  0 |  3\n"
+        + "Blithering Badgers             |  1 |  0 |  0 |  1 |  0";
+
+    assert_eq!(tournament::tally(&input), expected);
+}
+
+#[test]
+fn try_tally_accepts_valid_input() {
+    let input = "Allegoric Alaskans;Blithering Badgers;win\n\n".to_string()
+        + "Blithering Badgers;Allegoric Alaskans;draw\n";
+    let expected = "".to_string()
+        + "Team                           | MP |  W |  D |  L |  P\n"
+        + "Allegoric Alaskans             |  2 |  1 |  1 |  0 |  4\n"
+        + "Blithering Badgers             |  2 |  0 |  1 |  1 |  1";
+
+    assert_eq!(tournament::try_tally(&input), Ok(expected));
+}
+
+#[test]
+fn try_tally_reports_an_unknown_result() {
+    let input = "Allegoric Alaskans;Blithering Badgers;win\n".to_string()
+        + "Allegoric Alaskans;Blithering Badgers;wn";
+    let error = tournament::try_tally(&input).unwrap_err();
+
+    assert_eq!(
+        error,
+        tournament::ParseError {
+            line: 2,
+            text: "Allegoric Alaskans;Blithering Badgers;wn".to_string(),
+            kind: tournament::ParseErrorKind::UnknownResult("wn".to_string()),
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "line 2: unknown result \"wn\" in \"Allegoric Alaskans;Blithering Badgers;wn\""
+    );
+}
+
+#[test]
+fn try_tally_reports_a_wrong_number_of_fields() {
+    let error = tournament::try_tally("Allegoric Alaskans;win").unwrap_err();
+
+    assert_eq!(error.line, 1);
+    assert_eq!(error.kind, tournament::ParseErrorKind::FieldCount(2));
+}
+
+#[test]
+fn try_tally_reports_an_empty_team_name() {
+    let error = tournament::try_tally("Allegoric Alaskans; ;draw").unwrap_err();
+
+    assert_eq!(error.kind, tournament::ParseErrorKind::EmptyTeamName);
+}
+
+#[test]
+fn try_tally_reports_a_team_playing_itself() {
+    let error = tournament::try_tally("Allegoric Alaskans;Allegoric Alaskans;win").unwrap_err();
+
+    assert_eq!(error.kind, tournament::ParseErrorKind::SelfMatch);
+}
+
+#[test]
+fn tally_lenient_returns_the_rejected_lines() {
+    let input = "Allegoric Alaskans;Blithering Badgers;win\n".to_string()
+        + "Blithering Badgers;Blithering Badgers;draw\n"
+        + "\n"
+        + "Courageous Californians;Allegoric Alaskans\n"
+        + "Courageous Californians;Blithering Badgers;loss";
+    let expected = "".to_string()
+        + "Team                           | MP |  W |  D |  L |  P\n"
+        + "Allegoric Alaskans             |  1 |  1 |  0 |  0 |  3\n"
+        + "Blithering Badgers             |  2 |  1 |  0 |  1 |  3\n"
+        + "Courageous Californians        |  1 |  0 |  0 |  1 |  0";
+    let (table, rejected) = tournament::tally_lenient(&input);
+
+    assert_eq!(table, expected);
+    assert_eq!(
+        rejected
+            .iter()
+            .map(|error| (error.line, error.kind.clone()))
+            .collect::<Vec<_>>(),
+        vec![
+            (2, tournament::ParseErrorKind::SelfMatch),
+            (4, tournament::ParseErrorKind::FieldCount(2)),
+        ]
+    );
+}
+
+#[test]
+fn points_can_be_configured() {
+    let rules = tournament::LeagueRules {
+        win: 2,
+        ..tournament::LeagueRules::default()
+    };
+    let input = "Allegoric Alaskans;Blithering Badgers;win\n".to_string()
+        + "Blithering Badgers;Courageous Californians;draw";
+    let expected = "".to_string()
+        + "Team                           | MP |  W |  D |  L |  P\n"
+        + "Allegoric Alaskans             |  1 |  1 |  0 |  0 |  2\n"
+        + "Blithering Badgers             |  2 |  0 |  1 |  1 |  1\n"
+        + "Courageous Californians        |  1 |  0 |  1 |  0 |  1";
+
+    assert_eq!(tournament::tally_with_rules(&input, &rules), expected);
+}
+
+#[test]
+fn away_wins_can_earn_a_bonus() {
+    let rules = tournament::LeagueRules {
+        away_win_bonus: 1,
+        ..tournament::LeagueRules::default()
+    };
+    let input = "Allegoric Alaskans;Blithering Badgers;win\n".to_string()
+        + "Courageous Californians;Devastating Donkeys;loss";
+    let expected = "".to_string()
+        + "Team                           | MP |  W |  D |  L |  P\n"
+        + "Devastating Donkeys            |  1 |  1 |  0 |  0 |  4\n"
+        + "Allegoric Alaskans             |  1 |  1 |  0 |  0 |  3\n"
+        + "Blithering Badgers             |  1 |  0 |  0 |  1 |  0\n"
+        + "Courageous Californians        |  1 |  0 |  0 |  1 |  0";
+
+    assert_eq!(tournament::tally_with_rules(&input, &rules), expected);
+}
+
+#[test]
+fn large_point_values_add_up() {
+    let rules = tournament::LeagueRules {
+        win: 65535,
+        away_win_bonus: 1,
+        ..tournament::LeagueRules::default()
+    };
+    let input = "Allegoric Alaskans;Blithering Badgers;win\n".to_string()
+        + "Allegoric Alaskans;Courageous Californians;win\n"
+        + "Devastating Donkeys;Allegoric Alaskans;loss";
+    let expected = "".to_string()
+        + "Team                           | MP |  W |  D |  L |  P\n"
+        + "Allegoric Alaskans             |  3 |  3 |  0 |  0 | 196606\n"
+        + "Blithering Badgers             |  1 |  0 |  0 |  1 |  0\n"
+        + "Courageous Californians        |  1 |  0 |  0 |  1 |  0\n"
+        + "Devastating Donkeys            |  1 |  0 |  0 |  1 |  0";
+
+    assert_eq!(tournament::tally_with_rules(&input, &rules), expected);
+
+    let mut league = tournament::League::new(rules);
+    for _ in 0..100 {
+        league
+            .add_match("Blithering Badgers;Allegoric Alaskans;loss")
+            .unwrap();
+    }
+    assert_eq!(
+        league.standings().to_csv().lines().nth(1),
+        Some("Allegoric Alaskans,100,100,0,0,6553600")
+    );
+}
+
+#[test]
+fn ties_broken_by_head_to_head_record() {
+    let rules = tournament::LeagueRules {
+        tiebreakers: vec![tournament::Tiebreaker::HeadToHead],
+        ..tournament::LeagueRules::default()
+    };
+    let input = "Blithering Badgers;Allegoric Alaskans;win\n".to_string()
+        + "Allegoric Alaskans;Courageous Californians;win\n"
+        + "Courageous Californians;Blithering Badgers;win";
+    let expected = "".to_string()
+        + "Team                           | MP |  W |  D |  L |  P\n"
+        + "Allegoric Alaskans             |  2 |  1 |  0 |  1 |  3\n"
+        + "Blithering Badgers             |  2 |  1 |  0 |  1 |  3\n"
+        + "Courageous Californians        |  2 |  1 |  0 |  1 |  3";
+
+    assert_eq!(tournament::tally_with_rules(&input, &rules), expected);
+
+    let input = input
+        + "\nBlithering Badgers;Devastating Donkeys;loss\n"
+        + "Devastating Donkeys;Allegoric Alaskans;loss";
+    let expected = "".to_string()
+        + "Team                           | MP |  W |  D |  L |  P\n"
+        + "Allegoric Alaskans             |  3 |  2 |  0 |  1 |  6\n"
+        + "Courageous Californians        |  2 |  1 |  0 |  1 |  3\n"
+        + "Devastating Donkeys            |  2 |  1 |  0 |  1 |  3\n"
+        + "Blithering Badgers             |  3 |  1 |  0 |  2 |  3";
+
+    assert_eq!(tournament::tally_with_rules(&input, &rules), expected);
+}
+
+#[test]
+fn tiebreakers_apply_in_order() {
+    let input = "Blithering Badgers;Allegoric Alaskans;win\n".to_string()
+        + "Allegoric Alaskans;Devastating Donkeys;win\n"
+        + "Allegoric Alaskans;Devastating Donkeys;win\n"
+        + "Blithering Badgers;Courageous Californians;draw\n"
+        + "Blithering Badgers;Courageous Californians;draw\n"
+        + "Blithering Badgers;Courageous Californians;draw";
+    let head_to_head_first = tournament::LeagueRules {
+        tiebreakers: vec![
+            tournament::Tiebreaker::HeadToHead,
+            tournament::Tiebreaker::Wins,
+        ],
+        ..tournament::LeagueRules::default()
+    };
+    let wins_first = tournament::LeagueRules {
+        tiebreakers: vec![
+            tournament::Tiebreaker::Wins,
+            tournament::Tiebreaker::HeadToHead,
+        ],
+        ..tournament::LeagueRules::default()
+    };
+    let header = "Team                           | MP |  W |  D |  L |  P\n";
+    let alaskans = "Allegoric Alaskans             |  3 |  2 |  0 |  1 |  6\n";
+    let badgers = "Blithering Badgers             |  4 |  1 |  3 |  0 |  6\n";
+    let rest = "".to_string()
+        + "Courageous Californians        |  3 |  0 |  3 |  0 |  3\n"
+        + "Devastating Donkeys            |  2 |  0 |  0 |  2 |  0";
+
+    assert_eq!(
+        tournament::tally_with_rules(&input, &head_to_head_first),
+        header.to_string() + badgers + alaskans + &rest
+    );
+    assert_eq!(
+        tournament::tally_with_rules(&input, &wins_first),
+        header.to_string() + alaskans + badgers + &rest
+    );
+}
+
+#[test]
+fn results_can_be_scores() {
+    let input = "Allegoric Alaskans;Blithering Badgers;3-1\n".to_string()
+        + "Blithering Badgers;Courageous Californians;2-2\n"
+        + "Courageous Californians;Allegoric Alaskans;0-1";
+    let expected = "".to_string()
+        + "Team                           | MP |  W |  D |  L | GF | GA |  GD |  P\n"
+        + "Allegoric Alaskans             |  2 |  2 |  0 |  0 |  4 |  1 |   3 |  6\n"
+        + "Blithering Badgers             |  2 |  0 |  1 |  1 |  3 |  5 |  -2 |  1\n"
+        + "Courageous Californians        |  2 |  0 |  1 |  1 |  2 |  3 |  -1 |  1";
+
+    assert_eq!(tournament::tally(&input), expected);
+}
+
+#[test]
+fn scores_and_results_can_be_mixed() {
+    let input = "Allegoric Alaskans;Blithering Badgers;win\n".to_string()
+        + "Blithering Badgers;Allegoric Alaskans;1-0";
+    let expected = "".to_string()
+        + "Team                           | MP |  W |  D |  L | GF | GA |  GD |  P\n"
+        + "Allegoric Alaskans             |  2 |  1 |  0 |  1 |  0 |  1 |  -1 |  3\n"
+        + "Blithering Badgers             |  2 |  1 |  0 |  1 |  1 |  0 |   1 |  3";
+
+    assert_eq!(tournament::tally(&input), expected);
+}
+
+#[test]
+fn malformed_scores_are_unknown_results() {
+    let error = tournament::try_tally("Allegoric Alaskans;Blithering Badgers;3-x").unwrap_err();
+
+    assert_eq!(
+        error.kind,
+        tournament::ParseErrorKind::UnknownResult("3-x".to_string())
+    );
+    let error = tournament::try_tally("Allegoric Alaskans;Blithering Badgers;+3-1").unwrap_err();
+
+    assert_eq!(
+        error.kind,
+        tournament::ParseErrorKind::UnknownResult("+3-1".to_string())
+    );
+}
+
+#[test]
+fn huge_scores_add_up() {
+    let input = "Allegoric Alaskans;Blithering Badgers;40000-0\n".to_string()
+        + "Allegoric Alaskans;Courageous Californians;40000-0";
+    let expected = "".to_string()
+        + "Team                           | MP |  W |  D |  L | GF | GA |  GD |  P\n"
+        + "Allegoric Alaskans             |  2 |  2 |  0 |  0 | 80000 |  0 | 80000 |  6\n"
+        + "Blithering Badgers             |  1 |  0 |  0 |  1 |  0 | 40000 | -40000 |  0\n"
+        + "Courageous Californians        |  1 |  0 |  0 |  1 |  0 | 40000 | -40000 |  0";
+
+    assert_eq!(tournament::tally(&input), expected);
+}
+
+#[test]
+fn ties_broken_by_goal_difference() {
+    let rules = tournament::LeagueRules {
+        tiebreakers: vec![tournament::Tiebreaker::GoalDifference],
+        ..tournament::LeagueRules::default()
+    };
+    let input = "Allegoric Alaskans;Courageous Californians;1-0\n".to_string()
+        + "Blithering Badgers;Courageous Californians;4-0";
+    let expected = "".to_string()
+        + "Team                           | MP |  W |  D |  L | GF | GA |  GD |  P\n"
+        + "Blithering Badgers             |  1 |  1 |  0 |  0 |  4 |  0 |   4 |  3\n"
+        + "Allegoric Alaskans             |  1 |  1 |  0 |  0 |  1 |  0 |   1 |  3\n"
+        + "Courageous Californians        |  2 |  0 |  0 |  2 |  0 |  5 |  -5 |  0";
+
+    assert_eq!(tournament::tally_with_rules(&input, &rules), expected);
+}
+
+fn standings() -> tournament::Standings {
+    let input = "Allegoric Alaskans;Blithering Badgers;win\n".to_string()
+        + "Devastating Donkeys;Courageous Californians;draw\n"
+        + "Devastating Donkeys;Allegoric Alaskans;win\n"
+        + "Courageous Californians;Blithering Badgers;loss\n"
+        + "Blithering Badgers;Devastating Donkeys;loss\n"
+        + "Allegoric Alaskans;Courageous Californians;win";
+    input.parse().unwrap()
+}
+
+#[test]
+fn standings_display_as_the_text_table() {
+    let expected = "".to_string()
+        + "Team                           | MP |  W |  D |  L |  P\n"
+        + "Devastating Donkeys            |  3 |  2 |  1 |  0 |  7\n"
+        + "Allegoric Alaskans             |  3 |  2 |  0 |  1 |  6\n"
+        + "Blithering Badgers             |  3 |  1 |  0 |  2 |  3\n"
+        + "Courageous Californians        |  3 |  0 |  1 |  2 |  1";
+
+    assert_eq!(standings().to_string(), expected);
+}
+
+#[test]
+fn standings_as_csv() {
+    let expected = "".to_string()
+        + "Team,MP,W,D,L,P\n"
+        + "Devastating Donkeys,3,2,1,0,7\n"
+        + "Allegoric Alaskans,3,2,0,1,6\n"
+        + "Blithering Badgers,3,1,0,2,3\n"
+        + "Courageous Californians,3,0,1,2,1\n";
+
+    assert_eq!(standings().to_csv(), expected);
+}
+
+#[test]
+fn standings_as_json() {
+    let expected = "".to_string()
+        + "[{\"team\":\"Devastating Donkeys\",\"matches\":3,\"wins\":2,\"draws\":1,\"losses\":0,\"points\":7},"
+        + "{\"team\":\"Allegoric Alaskans\",\"matches\":3,\"wins\":2,\"draws\":0,\"losses\":1,\"points\":6},"
+        + "{\"team\":\"Blithering Badgers\",\"matches\":3,\"wins\":1,\"draws\":0,\"losses\":2,\"points\":3},"
+        + "{\"team\":\"Courageous Californians\",\"matches\":3,\"wins\":0,\"draws\":1,\"losses\":2,\"points\":1}]";
+
+    assert_eq!(standings().to_json(), expected);
+}
+
+#[test]
+fn standings_as_markdown() {
+    let expected = "".to_string()
+        + "| Team | MP | W | D | L | P |\n"
+        + "| :--- | ---: | ---: | ---: | ---: | ---: |\n"
+        + "| Devastating Donkeys | 3 | 2 | 1 | 0 | 7 |\n"
+        + "| Allegoric Alaskans | 3 | 2 | 0 | 1 | 6 |\n"
+        + "| Blithering Badgers | 3 | 1 | 0 | 2 | 3 |\n"
+        + "| Courageous Californians | 3 | 0 | 1 | 2 | 1 |\n";
+
+    assert_eq!(standings().to_markdown(), expected);
+}
+
+#[test]
+fn standings_as_html() {
+    let expected = "".to_string()
+        + "<table>\n"
+        + "  <thead>\n"
+        + "    <tr><th>Team</th><th>MP</th><th>W</th><th>D</th><th>L</th><th>P</th></tr>\n"
+        + "  </thead>\n"
+        + "  <tbody>\n"
+        + "    <tr><td>Devastating Donkeys</td><td>3</td><td>2</td><td>1</td><td>0</td><td>7</td></tr>\n"
+        + "    <tr><td>Allegoric Alaskans</td><td>3</td><td>2</td><td>0</td><td>1</td><td>6</td></tr>\n"
+        + "    <tr><td>Blithering Badgers</td><td>3</td><td>1</td><td>0</td><td>2</td><td>3</td></tr>\n"
+        + "    <tr><td>Courageous Californians</td><td>3</td><td>0</td><td>1</td><td>2</td><td>1</td></tr>\n"
+        + "  </tbody>\n"
+        + "</table>\n";
+
+    assert_eq!(standings().to_html(), expected);
+}
+
+#[test]
+fn standings_escape_team_names() {
+    let standings = "Rock & \"Roll\", <Ltd>;Pipe|Dream;2-1"
+        .parse::<tournament::Standings>()
+        .unwrap();
+
+    assert_eq!(
+        standings.to_csv().lines().nth(1),
+        Some("\"Rock & \"\"Roll\"\", <Ltd>\",1,1,0,0,2,1,1,3")
+    );
+    assert!(standings
+        .to_json()
+        .starts_with("[{\"team\":\"Rock & \\\"Roll\\\", <Ltd>\",\"matches\":1,"));
+    assert_eq!(
+        standings.to_markdown().lines().nth(3),
+        Some("| Pipe\\|Dream | 1 | 0 | 0 | 1 | 1 | 2 | -1 | 0 |")
+    );
+    assert!(standings
+        .to_html()
+        .contains("<td>Rock &amp; &quot;Roll&quot;, &lt;Ltd&gt;</td>"));
+}
+
+#[test]
+fn standings_report_parse_errors() {
+    let error = "Allegoric Alaskans;Blithering Badgers;wn"
+        .parse::<tournament::Standings>()
+        .unwrap_err();
+
+    assert_eq!(
+        error.kind,
+        tournament::ParseErrorKind::UnknownResult("wn".to_string())
+    );
+}
+
+#[test]
+fn a_league_tallies_matches_as_they_are_added() {
+    let mut league = tournament::League::default();
+    assert_eq!(league.rounds(), 0);
+    assert!(league
+        .add_match("Allegoric Alaskans;Blithering Badgers;win")
+        .is_ok());
+    assert_eq!(
+        league.add_match("Allegoric Alaskans;Blithering Badgers;wn"),
+        Err(tournament::ParseErrorKind::UnknownResult("wn".to_string()))
+    );
+    assert!(league
+        .add_match("Courageous Californians;Devastating Donkeys;draw")
+        .is_ok());
+    let expected = "".to_string()
+        + "Team                           | MP |  W |  D |  L |  P\n"
+        + "Allegoric Alaskans             |  1 |  1 |  0 |  0 |  3\n"
+        + "Courageous Californians        |  1 |  0 |  1 |  0 |  1\n"
+        + "Devastating Donkeys            |  1 |  0 |  1 |  0 |  1\n"
+        + "Blithering Badgers             |  1 |  0 |  0 |  1 |  0";
+
+    assert_eq!(league.rounds(), 1);
+    assert_eq!(league.standings().to_string(), expected);
+}
+
+fn league() -> tournament::League {
+    let rounds = [
+        [
+            "Allegoric Alaskans;Blithering Badgers;win",
+            "Courageous Californians;Devastating Donkeys;draw",
+        ],
+        [
+            "Devastating Donkeys;Allegoric Alaskans;win",
+            "Blithering Badgers;Courageous Californians;win",
+        ],
+        [
+            "Allegoric Alaskans;Courageous Californians;loss",
+            "Devastating Donkeys;Blithering Badgers;draw",
+        ],
+    ];
+    let mut league = tournament::League::new(tournament::LeagueRules::default());
+    for round in &rounds {
+        for result in round {
+            league.add_match(result).unwrap();
+        }
+        league.end_round();
+    }
+    league
+}
+
+#[test]
+fn a_league_keeps_the_table_of_every_round() {
+    let league = league();
+    let after_two_rounds = "".to_string()
+        + "Team                           | MP |  W |  D |  L |  P\n"
+        + "Devastating Donkeys            |  2 |  1 |  1 |  0 |  4\n"
+        + "Allegoric Alaskans             |  2 |  1 |  0 |  1 |  3\n"
+        + "Blithering Badgers             |  2 |  1 |  0 |  1 |  3\n"
+        + "Courageous Californians        |  2 |  0 |  1 |  1 |  1";
+
+    assert_eq!(league.rounds(), 3);
+    assert_eq!(
+        league.standings_after(0).unwrap().to_string(),
+        "Team                           | MP |  W |  D |  L |  P"
+    );
+    assert_eq!(
+        league.standings_after(2).unwrap().to_string(),
+        after_two_rounds
+    );
+    assert_eq!(league.standings_after(3), Some(league.standings()));
+    assert_eq!(league.standings_after(4), None);
+}
+
+#[test]
+fn a_league_reports_position_changes() {
+    let league = league();
+    let movement = |team: &str, position, previous| tournament::Movement {
+        team: team.to_string(),
+        position,
+        previous,
+    };
+
+    assert_eq!(
+        league.position_changes(1),
+        Some(vec![
+            movement("Allegoric Alaskans", 1, None),
+            movement("Courageous Californians", 2, None),
+            movement("Devastating Donkeys", 3, None),
+            movement("Blithering Badgers", 4, None),
+        ])
+    );
+    assert_eq!(
+        league.position_changes(3),
+        Some(vec![
+            movement("Devastating Donkeys", 1, Some(1)),
+            movement("Blithering Badgers", 2, Some(3)),
+            movement("Courageous Californians", 3, Some(4)),
+            movement("Allegoric Alaskans", 4, Some(2)),
+        ])
+    );
+    assert_eq!(league.position_changes(0), None);
+    assert_eq!(league.position_changes(4), None);
+}
+
+#[test]
+fn a_league_gives_the_form_of_the_last_five_matches() {
+    let mut league = league();
+    for result in ["win", "win", "loss", "draw"] {
+        league
+            .add_match(&format!(
+                "Allegoric Alaskans;Devastating Donkeys;{}",
+                result
+            ))
+            .unwrap();
+    }
+    let form = |team| {
+        league
+            .form(team)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<String>()
+    };
+
+    assert_eq!(form("Allegoric Alaskans"), "LWWLD");
+    assert_eq!(form("Blithering Badgers"), "LWD");
+    assert_eq!(form("Eager Eagles"), "");
+}